@@ -0,0 +1,145 @@
+//! The kernel's heap: a first-fit list of the free blocks in the `.heap` section of the linker
+//! script, sorted by address so neighbours are merged back when freed.
+
+use crate::interrupt;
+use crate::uart_pl011::{MutexTrait, NullLock};
+use core::alloc::{GlobalAlloc, Layout};
+use core::cell::UnsafeCell;
+use core::mem::{align_of, size_of};
+use core::ptr;
+
+/// Every block starts and ends on it, so a free block always fits in what's left around an
+/// allocation.
+const BLOCK_ALIGN: usize = 16;
+
+#[repr(C, align(16))]
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+struct FreeList {
+    head: *mut FreeBlock,
+}
+
+pub struct KernelAllocator {
+    free: NullLock<FreeList>,
+}
+
+#[global_allocator]
+static ALLOCATOR: KernelAllocator = KernelAllocator::new();
+
+/// Hands the `.heap` section to the allocator.
+///
+/// # Safety
+///
+/// - Only once, before anything is allocated.
+pub unsafe fn init() {
+    extern "Rust" {
+        static __heap_start: UnsafeCell<()>;
+        static __heap_end_exclusive: UnsafeCell<()>;
+    }
+    let start = __heap_start.get() as usize;
+    let end = __heap_end_exclusive.get() as usize;
+    (&ALLOCATOR.free).lock(|list| list.free(start, end - start));
+}
+
+impl KernelAllocator {
+    const fn new() -> Self {
+        Self {
+            free: NullLock::new(FreeList {
+                head: ptr::null_mut(),
+            }),
+        }
+    }
+}
+
+unsafe impl GlobalAlloc for KernelAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        // IRQ handlers could allocate too.
+        interrupt::without_irqs(|| (&self.free).lock(|list| list.allocate(layout)))
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let size = block_size(layout);
+        interrupt::without_irqs(|| (&self.free).lock(|list| list.free(ptr as usize, size)));
+    }
+}
+
+impl FreeList {
+    /// Null if no free block is big enough.
+    unsafe fn allocate(&mut self, layout: Layout) -> *mut u8 {
+        let size = block_size(layout);
+        let align = layout.align().max(BLOCK_ALIGN);
+        let mut previous: *mut FreeBlock = ptr::null_mut();
+        let mut current = self.head;
+        while !current.is_null() {
+            let start = current as usize;
+            let end = start + (*current).size;
+            let allocation = start.next_multiple_of(align);
+            if allocation + size <= end {
+                // what's left on both sides stays free.
+                let mut next = (*current).next;
+                if allocation + size < end {
+                    next = write_block(allocation + size, end - allocation - size, next);
+                }
+                if allocation > start {
+                    next = write_block(start, allocation - start, next);
+                }
+                self.link(previous, next);
+                return allocation as *mut u8;
+            }
+            previous = current;
+            current = (*current).next;
+        }
+        ptr::null_mut()
+    }
+
+    /// Adds `start..start + size` back, merged with the free blocks right before and after it.
+    unsafe fn free(&mut self, start: usize, size: usize) {
+        let mut previous: *mut FreeBlock = ptr::null_mut();
+        let mut next = self.head;
+        while !next.is_null() && (next as usize) < start {
+            previous = next;
+            next = (*next).next;
+        }
+
+        let mut block = write_block(start, size, next);
+        if !next.is_null() && start + size == next as usize {
+            (*block).size += (*next).size;
+            (*block).next = (*next).next;
+        }
+        if !previous.is_null() && previous as usize + (*previous).size == start {
+            (*previous).size += (*block).size;
+            (*previous).next = (*block).next;
+            block = previous;
+        }
+        if block != previous {
+            self.link(previous, block);
+        }
+    }
+
+    /// Makes `next` follow `previous`, or the first block without one.
+    unsafe fn link(&mut self, previous: *mut FreeBlock, next: *mut FreeBlock) {
+        if previous.is_null() {
+            self.head = next;
+        } else {
+            (*previous).next = next;
+        }
+    }
+}
+
+/// The size taken by `layout`, rounded so blocks stay aligned.
+fn block_size(layout: Layout) -> usize {
+    layout
+        .size()
+        .max(size_of::<FreeBlock>())
+        .next_multiple_of(BLOCK_ALIGN)
+}
+
+unsafe fn write_block(start: usize, size: usize, next: *mut FreeBlock) -> *mut FreeBlock {
+    debug_assert_eq!(start % align_of::<FreeBlock>(), 0);
+    let block = start as *mut FreeBlock;
+    block.write(FreeBlock { size, next });
+    block
+}
@@ -0,0 +1,164 @@
+//! Exceptions: `exception.s` sends all of them to `handle_exception`. IRQs go to the interrupt
+//! controller, any other exception is unexpected: it's reported over `PL011_UART` and the core
+//! parks.
+//!
+//! The report is made of `key: value` lines, between `CPU EXCEPTION` and `Parking the core.`:
+//! ```text
+//! CPU EXCEPTION: synchronous, current EL with SP_ELx
+//! ESR_EL1: 0x96000061
+//!     class: 0x25, data abort, current EL
+//!     fault: alignment fault, write
+//! FAR_EL1: 0x0000000000080001
+//! ELR_EL1: 0x00000000000812c4
+//! SPSR_EL1: 0x600003c5
+//! SP: 0x000000000007fe40
+//! x0: 0x0000000000000000 x1: 0x0000000000000001 x2: ...
+//! ...
+//! Parking the core.
+//! ```
+
+use crate::interrupt::INTERRUPT_CONTROLLER;
+use crate::{print, println};
+use core::arch::global_asm;
+use core::cell::UnsafeCell;
+use core::mem::size_of;
+use cortex_a::asm;
+use cortex_a::asm::barrier;
+use cortex_a::registers::{FAR_EL1, VBAR_EL1};
+use tock_registers::interfaces::{Readable, Writeable};
+
+global_asm!(include_str!("exception.s"));
+
+// ESR_EL1 fields.
+const ESR_EC_SHIFT: u64 = 26;
+const ESR_EC_MASK: u64 = 0x3f;
+const ESR_ISS_MASK: u64 = 0x1ff_ffff;
+// in the ISS of data and instruction aborts.
+const ISS_FSC_MASK: u64 = 0x3f;
+const ISS_WNR: u64 = 1 << 6;
+
+const EC_INSTRUCTION_ABORT_LOWER_EL: u64 = 0x20;
+const EC_INSTRUCTION_ABORT_CURRENT_EL: u64 = 0x21;
+const EC_DATA_ABORT_LOWER_EL: u64 = 0x24;
+const EC_DATA_ABORT_CURRENT_EL: u64 = 0x25;
+
+/// The table entry of IRQs taken while running the kernel, at EL1 with SP_EL1.
+const VECTOR_IRQ_CURRENT_EL_SPX: u64 = 5;
+
+/// What was running when the exception was taken, saved on the stack by `exception.s`.
+#[repr(C)]
+pub struct ExceptionContext {
+    /// x0 to x30.
+    gpr: [u64; 31],
+    elr_el1: u64,
+    spsr_el1: u64,
+    esr_el1: u64,
+}
+
+/// Installs the exception vector table.
+///
+/// # Safety
+///
+/// - The report can only be seen once `PL011_UART` is initialized.
+pub unsafe fn init() {
+    extern "Rust" {
+        static __exception_vector_start: UnsafeCell<()>;
+    }
+    VBAR_EL1.set(__exception_vector_start.get() as u64);
+    // the vectors are used from the next instruction on.
+    barrier::isb(barrier::SY);
+}
+
+/// `vector` is the index of the table entry taken: which kind of exception, from where.
+#[no_mangle]
+extern "C" fn handle_exception(context: &mut ExceptionContext, vector: u64) {
+    if vector == VECTOR_IRQ_CURRENT_EL_SPX {
+        INTERRUPT_CONTROLLER.handle_pending();
+        return;
+    }
+    report(context, vector);
+    loop {
+        asm::wfe();
+    }
+}
+
+fn report(context: &ExceptionContext, vector: u64) {
+    const KINDS: [&str; 4] = ["synchronous", "IRQ", "FIQ", "SError"];
+    const ORIGINS: [&str; 4] = [
+        "current EL with SP_EL0",
+        "current EL with SP_ELx",
+        "lower EL in AArch64",
+        "lower EL in AArch32",
+    ];
+    let vector = vector as usize;
+    let (origin, kind) = (vector / 4 % 4, vector % 4);
+    println!("CPU EXCEPTION: {}, {}", KINDS[kind], ORIGINS[origin]);
+
+    let esr = context.esr_el1;
+    let class = (esr >> ESR_EC_SHIFT) & ESR_EC_MASK;
+    let iss = esr & ESR_ISS_MASK;
+    println!("ESR_EL1: {:#010x}", esr);
+    println!("    class: {:#04x}, {}", class, exception_class(class));
+    match class {
+        EC_DATA_ABORT_LOWER_EL | EC_DATA_ABORT_CURRENT_EL => {
+            let access = if iss & ISS_WNR == 0 { "read" } else { "write" };
+            println!("    fault: {}, {}", fault_status(iss), access);
+        }
+        EC_INSTRUCTION_ABORT_LOWER_EL | EC_INSTRUCTION_ABORT_CURRENT_EL => {
+            println!("    fault: {}", fault_status(iss));
+        }
+        _ => println!("    iss: {:#x}", iss),
+    }
+    println!("FAR_EL1: {:#018x}", FAR_EL1.get());
+    println!("ELR_EL1: {:#018x}", context.elr_el1);
+    println!("SPSR_EL1: {:#010x}", context.spsr_el1);
+    // before the context was pushed.
+    let sp = context as *const ExceptionContext as usize + size_of::<ExceptionContext>();
+    println!("SP: {:#018x}", sp);
+    for (i, registers) in context.gpr.chunks(4).enumerate() {
+        for (j, register) in registers.iter().enumerate() {
+            let separator = if j == 0 { "" } else { " " };
+            print!("{}x{}: {:#018x}", separator, i * 4 + j, register);
+        }
+        println!();
+    }
+    println!("Parking the core.");
+}
+
+fn exception_class(class: u64) -> &'static str {
+    match class {
+        0x00 => "unknown reason",
+        0x01 => "trapped WFI or WFE",
+        0x07 => "trapped SIMD or floating point access",
+        0x0e => "illegal execution state",
+        0x15 => "SVC instruction",
+        0x18 => "trapped MSR, MRS or system instruction",
+        EC_INSTRUCTION_ABORT_LOWER_EL => "instruction abort, lower EL",
+        EC_INSTRUCTION_ABORT_CURRENT_EL => "instruction abort, current EL",
+        0x22 => "PC alignment fault",
+        EC_DATA_ABORT_LOWER_EL => "data abort, lower EL",
+        EC_DATA_ABORT_CURRENT_EL => "data abort, current EL",
+        0x26 => "SP alignment fault",
+        0x2c => "trapped floating point exception",
+        0x2f => "SError",
+        0x30 | 0x31 => "breakpoint",
+        0x32 | 0x33 => "software step",
+        0x34 | 0x35 => "watchpoint",
+        0x3c => "BRK instruction",
+        _ => "reserved",
+    }
+}
+
+/// The fault status code of a data or instruction abort.
+fn fault_status(iss: u64) -> &'static str {
+    match iss & ISS_FSC_MASK {
+        0b00_0000..=0b00_0011 => "address size fault",
+        0b00_0100..=0b00_0111 => "translation fault",
+        0b00_1001..=0b00_1011 => "access flag fault",
+        0b00_1101..=0b00_1111 => "permission fault",
+        0b01_0000 => "synchronous external abort",
+        0b10_0001 => "alignment fault",
+        0b11_0000 => "TLB conflict abort",
+        _ => "other fault",
+    }
+}
@@ -0,0 +1,96 @@
+// The exception vector table, installed in VBAR_EL1 by `exception::init`.
+//
+// Every entry saves the interrupted context on the stack, as an `ExceptionContext`, and calls
+// `handle_exception` with it and the entry's index. If that returns, the context is restored
+// and execution goes back to where it was interrupted.
+
+// The size of `ExceptionContext`: x0 to x30, ELR_EL1, SPSR_EL1 and ESR_EL1.
+.equ CONTEXT_SIZE, 16 * 17
+
+.macro VECTOR_ENTRY index
+.org __exception_vector_start + \index * 0x80
+	sub	sp, sp, #CONTEXT_SIZE
+
+	stp	x0, x1, [sp, #16 * 0]
+	stp	x2, x3, [sp, #16 * 1]
+	stp	x4, x5, [sp, #16 * 2]
+	stp	x6, x7, [sp, #16 * 3]
+	stp	x8, x9, [sp, #16 * 4]
+	stp	x10, x11, [sp, #16 * 5]
+	stp	x12, x13, [sp, #16 * 6]
+	stp	x14, x15, [sp, #16 * 7]
+	stp	x16, x17, [sp, #16 * 8]
+	stp	x18, x19, [sp, #16 * 9]
+	stp	x20, x21, [sp, #16 * 10]
+	stp	x22, x23, [sp, #16 * 11]
+	stp	x24, x25, [sp, #16 * 12]
+	stp	x26, x27, [sp, #16 * 13]
+	stp	x28, x29, [sp, #16 * 14]
+
+	mrs	x1, ELR_EL1
+	mrs	x2, SPSR_EL1
+	mrs	x3, ESR_EL1
+	stp	lr, x1, [sp, #16 * 15]
+	stp	x2, x3, [sp, #16 * 16]
+
+	mov	x0, sp
+	mov	x1, #\index
+	bl	handle_exception
+	b	__exception_restore_context
+.endm
+
+.section .text
+
+// 2KiB aligned, each entry is 0x80 bytes long: `.org` fails to assemble if one gets longer.
+.align 11
+__exception_vector_start:
+	// Current EL, with SP_EL0.
+	VECTOR_ENTRY 0
+	VECTOR_ENTRY 1
+	VECTOR_ENTRY 2
+	VECTOR_ENTRY 3
+	// Current EL, with SP_ELx.
+	VECTOR_ENTRY 4
+	VECTOR_ENTRY 5
+	VECTOR_ENTRY 6
+	VECTOR_ENTRY 7
+	// Lower EL, in AArch64.
+	VECTOR_ENTRY 8
+	VECTOR_ENTRY 9
+	VECTOR_ENTRY 10
+	VECTOR_ENTRY 11
+	// Lower EL, in AArch32.
+	VECTOR_ENTRY 12
+	VECTOR_ENTRY 13
+	VECTOR_ENTRY 14
+	VECTOR_ENTRY 15
+.org __exception_vector_start + 16 * 0x80
+
+__exception_restore_context:
+	ldp	x19, x20, [sp, #16 * 16]
+	ldp	lr, x21, [sp, #16 * 15]
+	msr	SPSR_EL1, x19
+	msr	ELR_EL1, x21
+
+	ldp	x0, x1, [sp, #16 * 0]
+	ldp	x2, x3, [sp, #16 * 1]
+	ldp	x4, x5, [sp, #16 * 2]
+	ldp	x6, x7, [sp, #16 * 3]
+	ldp	x8, x9, [sp, #16 * 4]
+	ldp	x10, x11, [sp, #16 * 5]
+	ldp	x12, x13, [sp, #16 * 6]
+	ldp	x14, x15, [sp, #16 * 7]
+	ldp	x16, x17, [sp, #16 * 8]
+	ldp	x18, x19, [sp, #16 * 9]
+	ldp	x20, x21, [sp, #16 * 10]
+	ldp	x22, x23, [sp, #16 * 11]
+	ldp	x24, x25, [sp, #16 * 12]
+	ldp	x26, x27, [sp, #16 * 13]
+	ldp	x28, x29, [sp, #16 * 14]
+
+	add	sp, sp, #CONTEXT_SIZE
+	eret
+
+.size	__exception_restore_context, . - __exception_restore_context
+.type	__exception_restore_context, function
+.global	__exception_vector_start
@@ -5,6 +5,8 @@ use cortex_a::registers::{CNTVOFF_EL2, ELR_EL2, HCR_EL2, SPSR_EL2, SP_EL1};
 use log::info;
 use tock_registers::interfaces::Writeable;
 
+pub mod exception;
+
 #[no_mangle]
 #[link_section = ".text._start_arguments"]
 pub static BOOT_CORE_ID: u64 = 0;
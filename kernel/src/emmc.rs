@@ -0,0 +1,417 @@
+//! Minimal driver for the SD card slot (the Arasan EMMC controller): single block reads and
+//! writes, polling only.
+//!
+//! The init sequence follows https://github.com/bztsrc/raspi3-tutorial/tree/master/0B_readsector
+//! and the "SD Host Controller Simplified Specification".
+use crate::mmio::{EMMC_START, GPIO_START};
+use crate::time::TIME_MANAGER;
+use crate::uart_pl011::MMIODerefWrapper;
+use core::time::Duration;
+use cortex_a::asm;
+use log::{debug, info};
+use space_invaders::TimeManagerInterface;
+use tock_registers::interfaces::{Readable, Writeable};
+use tock_registers::register_structs;
+use tock_registers::registers::{ReadOnly, ReadWrite};
+
+pub const BLOCK_SIZE: usize = 512;
+
+register_structs! {
+    #[allow(non_snake_case)]
+    EmmcRegisterBlock {
+        (0x00 => ARG2: ReadWrite<u32>),
+        (0x04 => BLKSIZECNT: ReadWrite<u32>),
+        (0x08 => ARG1: ReadWrite<u32>),
+        (0x0c => CMDTM: ReadWrite<u32>),
+        (0x10 => RESP0: ReadOnly<u32>),
+        (0x14 => RESP1: ReadOnly<u32>),
+        (0x18 => RESP2: ReadOnly<u32>),
+        (0x1c => RESP3: ReadOnly<u32>),
+        (0x20 => DATA: ReadWrite<u32>),
+        (0x24 => STATUS: ReadOnly<u32>),
+        (0x28 => CONTROL0: ReadWrite<u32>),
+        (0x2c => CONTROL1: ReadWrite<u32>),
+        (0x30 => INTERRUPT: ReadWrite<u32>),
+        (0x34 => INT_MASK: ReadWrite<u32>),
+        (0x38 => INT_EN: ReadWrite<u32>),
+        (0x3c => CONTROL2: ReadWrite<u32>),
+        (0x40 => _reserved),
+        (0xfc => SLOTISR_VER: ReadOnly<u32>),
+        (0x100 => @END),
+    }
+}
+
+register_structs! {
+    #[allow(non_snake_case)]
+    GpioRegisterBlock {
+        (0x00 => _reserved1),
+        (0x10 => GPFSEL4: ReadWrite<u32>),
+        (0x14 => GPFSEL5: ReadWrite<u32>),
+        (0x18 => _reserved2),
+        (0x68 => GPHEN1: ReadWrite<u32>),
+        (0x6c => _reserved3),
+        (0x94 => GPPUD: ReadWrite<u32>),
+        (0x98 => _reserved4),
+        (0x9c => GPPUDCLK1: ReadWrite<u32>),
+        (0xa0 => @END),
+    }
+}
+
+// commands, already encoded for CMDTM.
+const CMD_NEED_APP: u32 = 0x8000_0000;
+const CMD_RSPNS_48: u32 = 0x0002_0000;
+const CMD_ERRORS_MASK: u32 = 0xfff9_c004;
+const CMD_RCA_MASK: u32 = 0xffff_0000;
+const CMD_GO_IDLE: u32 = 0x0000_0000;
+const CMD_ALL_SEND_CID: u32 = 0x0201_0000;
+const CMD_SEND_REL_ADDR: u32 = 0x0302_0000;
+const CMD_CARD_SELECT: u32 = 0x0703_0000;
+const CMD_SEND_IF_COND: u32 = 0x0802_0000;
+const CMD_READ_SINGLE: u32 = 0x1122_0010;
+const CMD_WRITE_SINGLE: u32 = 0x1822_0000;
+const CMD_APP_CMD: u32 = 0x3700_0000;
+const CMD_SET_BUS_WIDTH: u32 = 0x0602_0000 | CMD_NEED_APP;
+const CMD_SEND_OP_COND: u32 = 0x2902_0000 | CMD_NEED_APP;
+const CMD_SEND_SCR: u32 = 0x3322_0010 | CMD_NEED_APP;
+
+// STATUS register.
+const SR_READ_AVAILABLE: u32 = 0x0000_0800;
+const SR_DAT_INHIBIT: u32 = 0x0000_0002;
+const SR_CMD_INHIBIT: u32 = 0x0000_0001;
+const SR_APP_CMD: u32 = 0x0000_0020;
+
+// INTERRUPT register.
+const INT_DATA_TIMEOUT: u32 = 0x0010_0000;
+const INT_CMD_TIMEOUT: u32 = 0x0001_0000;
+const INT_READ_RDY: u32 = 0x0000_0020;
+const INT_WRITE_RDY: u32 = 0x0000_0010;
+const INT_DATA_DONE: u32 = 0x0000_0002;
+const INT_CMD_DONE: u32 = 0x0000_0001;
+const INT_ERROR_MASK: u32 = 0x017e_8000;
+
+// CONTROL0 and CONTROL1 registers.
+const C0_HCTL_DWIDTH: u32 = 0x0000_0002;
+const C1_SRST_HC: u32 = 0x0100_0000;
+const C1_TOUNIT_MAX: u32 = 0x000e_0000;
+const C1_CLK_EN: u32 = 0x0000_0004;
+const C1_CLK_STABLE: u32 = 0x0000_0002;
+const C1_CLK_INTLEN: u32 = 0x0000_0001;
+
+// SLOTISR_VER register.
+const HOST_SPEC_NUM: u32 = 0x00ff_0000;
+const HOST_SPEC_NUM_SHIFT: u32 = 16;
+const HOST_SPEC_V2: u32 = 1;
+
+// ACMD41 argument and response.
+const ACMD41_ARG_HC: u32 = 0x51ff_8000;
+const ACMD41_VOLTAGE: u32 = 0x00ff_8000;
+const ACMD41_CMD_COMPLETE: u32 = 0x8000_0000;
+const ACMD41_CMD_CCS: u32 = 0x4000_0000;
+
+// SCR register of the card.
+const SCR_SD_BUS_WIDTH_4: u32 = 0x0000_0400;
+
+const BASE_CLOCK_HZ: u32 = 41_666_666;
+const SETUP_CLOCK_HZ: u32 = 400_000;
+const NORMAL_CLOCK_HZ: u32 = 25_000_000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EmmcError {
+    /// The card or the controller didn't answer in time.
+    Timeout,
+    /// The controller reported an error.
+    Error,
+    /// The card doesn't support the 3.3V range.
+    UnsupportedVoltage,
+}
+
+pub struct Emmc {
+    registers: MMIODerefWrapper<EmmcRegisterBlock>,
+    gpio: MMIODerefWrapper<GpioRegisterBlock>,
+    host_version: u32,
+    // relative card address, given by the card during init.
+    rca: u32,
+    // high capacity cards are addressed by block, the others by byte.
+    high_capacity: bool,
+}
+
+fn wait_ms(ms: u64) {
+    TIME_MANAGER.sleep(Duration::from_millis(ms));
+}
+
+fn wait_cycles(cycles: usize) {
+    for _ in 0..cycles {
+        asm::nop();
+    }
+}
+
+impl Emmc {
+    /// # Safety
+    ///
+    /// - Only one instance must exist, it owns the SD card GPIO pins and controller.
+    pub const unsafe fn new() -> Self {
+        Self {
+            registers: MMIODerefWrapper::new(EMMC_START),
+            gpio: MMIODerefWrapper::new(GPIO_START),
+            host_version: 0,
+            rca: 0,
+            high_capacity: false,
+        }
+    }
+
+    /// Gets the card ready for reading and writing blocks.
+    pub fn init(&mut self) -> Result<(), EmmcError> {
+        self.init_gpio();
+        self.host_version =
+            (self.registers.SLOTISR_VER.get() & HOST_SPEC_NUM) >> HOST_SPEC_NUM_SHIFT;
+
+        // reset the controller.
+        self.registers.CONTROL0.set(0);
+        self.registers
+            .CONTROL1
+            .set(self.registers.CONTROL1.get() | C1_SRST_HC);
+        self.wait_for(1000, |r| r.CONTROL1.get() & C1_SRST_HC == 0)?;
+        self.registers
+            .CONTROL1
+            .set(self.registers.CONTROL1.get() | C1_CLK_INTLEN | C1_TOUNIT_MAX);
+        wait_ms(10);
+
+        self.set_clock(SETUP_CLOCK_HZ)?;
+        self.registers.INT_EN.set(0xffff_ffff);
+        self.registers.INT_MASK.set(0xffff_ffff);
+        self.rca = 0;
+
+        self.command(CMD_GO_IDLE, 0)?;
+        self.command(CMD_SEND_IF_COND, 0x0000_01aa)?;
+        let mut response = 0;
+        for _ in 0..6 {
+            wait_cycles(400);
+            response = self.command(CMD_SEND_OP_COND, ACMD41_ARG_HC)?;
+            if response & ACMD41_CMD_COMPLETE != 0 {
+                break;
+            }
+        }
+        if response & ACMD41_CMD_COMPLETE == 0 {
+            return Err(EmmcError::Timeout);
+        }
+        if response & ACMD41_VOLTAGE == 0 {
+            return Err(EmmcError::UnsupportedVoltage);
+        }
+        self.high_capacity = response & ACMD41_CMD_CCS != 0;
+
+        self.command(CMD_ALL_SEND_CID, 0)?;
+        self.rca = self.command(CMD_SEND_REL_ADDR, 0)?;
+        self.set_clock(NORMAL_CLOCK_HZ)?;
+        self.command(CMD_CARD_SELECT, self.rca)?;
+        self.wait_status(SR_DAT_INHIBIT)?;
+
+        // the SCR tells whether the card supports a 4 bit bus.
+        self.registers.BLKSIZECNT.set((1 << 16) | 8);
+        self.command(CMD_SEND_SCR, 0)?;
+        self.wait_interrupt(INT_READ_RDY)?;
+        let mut scr = [0; 2];
+        for word in &mut scr {
+            self.wait_for(100, |r| r.STATUS.get() & SR_READ_AVAILABLE != 0)?;
+            *word = self.registers.DATA.get();
+        }
+        if scr[0] & SCR_SD_BUS_WIDTH_4 != 0 {
+            self.command(CMD_SET_BUS_WIDTH, self.rca | 2)?;
+            self.registers
+                .CONTROL0
+                .set(self.registers.CONTROL0.get() | C0_HCTL_DWIDTH);
+        }
+        info!(
+            "SD card ready, high capacity: {}, host version: {}",
+            self.high_capacity, self.host_version
+        );
+        Ok(())
+    }
+
+    pub fn read_block(&mut self, lba: u32, buffer: &mut [u8; BLOCK_SIZE]) -> Result<(), EmmcError> {
+        self.wait_status(SR_DAT_INHIBIT)?;
+        self.registers.BLKSIZECNT.set((1 << 16) | BLOCK_SIZE as u32);
+        self.command(CMD_READ_SINGLE, self.address(lba))?;
+        self.wait_interrupt(INT_READ_RDY)?;
+        for word in buffer.chunks_exact_mut(4) {
+            word.copy_from_slice(&self.registers.DATA.get().to_le_bytes());
+        }
+        self.wait_interrupt(INT_DATA_DONE)
+    }
+
+    pub fn write_block(&mut self, lba: u32, buffer: &[u8; BLOCK_SIZE]) -> Result<(), EmmcError> {
+        self.wait_status(SR_DAT_INHIBIT)?;
+        self.registers.BLKSIZECNT.set((1 << 16) | BLOCK_SIZE as u32);
+        self.command(CMD_WRITE_SINGLE, self.address(lba))?;
+        self.wait_interrupt(INT_WRITE_RDY)?;
+        for word in buffer.chunks_exact(4) {
+            self.registers
+                .DATA
+                .set(u32::from_le_bytes([word[0], word[1], word[2], word[3]]));
+        }
+        self.wait_interrupt(INT_DATA_DONE)
+    }
+
+    fn address(&self, lba: u32) -> u32 {
+        if self.high_capacity {
+            lba
+        } else {
+            lba * BLOCK_SIZE as u32
+        }
+    }
+
+    // GPIO 47 is card detect, 48 clock, 49 command, 50-53 data: all on alternate function 3.
+    fn init_gpio(&self) {
+        let gpio = &self.gpio;
+        // 3 bits per pin: 47 is an input, 48 and 49 on GPFSEL4, 50-53 on GPFSEL5.
+        let fsel4 = gpio.GPFSEL4.get() & !0x00e0_0000;
+        gpio.GPFSEL4.set(fsel4 | 0x3f00_0000);
+        gpio.GPFSEL5.set(gpio.GPFSEL5.get() | 0x0000_0fff);
+        // pull up on all of them, bit 15 is pin 47.
+        gpio.GPPUD.set(2);
+        wait_cycles(150);
+        gpio.GPPUDCLK1.set(0x003f_8000);
+        wait_cycles(150);
+        gpio.GPPUD.set(0);
+        gpio.GPPUDCLK1.set(0);
+        gpio.GPHEN1.set(gpio.GPHEN1.get() | (1 << 15));
+    }
+
+    fn set_clock(&mut self, frequency: u32) -> Result<(), EmmcError> {
+        self.wait_for(1000, |r| {
+            r.STATUS.get() & (SR_CMD_INHIBIT | SR_DAT_INHIBIT) == 0
+        })?;
+        self.registers
+            .CONTROL1
+            .set(self.registers.CONTROL1.get() & !C1_CLK_EN);
+        wait_ms(10);
+
+        let divisor = BASE_CLOCK_HZ / frequency;
+        // older hosts only take powers of 2, as a shift.
+        let shift = match divisor - 1 {
+            0 => 0,
+            x => (32 - x.leading_zeros()).saturating_sub(1).min(7),
+        };
+        let mut divisor = if self.host_version > HOST_SPEC_V2 {
+            divisor
+        } else {
+            1 << shift
+        };
+        if divisor <= 2 {
+            divisor = 2;
+        }
+        let high_bits = if self.host_version > HOST_SPEC_V2 {
+            (divisor & 0x300) >> 2
+        } else {
+            0
+        };
+        let divisor = ((divisor & 0x0ff) << 8) | high_bits;
+        self.registers
+            .CONTROL1
+            .set((self.registers.CONTROL1.get() & 0xffff_003f) | divisor);
+        wait_ms(10);
+        self.registers
+            .CONTROL1
+            .set(self.registers.CONTROL1.get() | C1_CLK_EN);
+        wait_ms(10);
+        self.wait_for(1000, |r| r.CONTROL1.get() & C1_CLK_STABLE != 0)?;
+        debug!("SD clock set to {frequency}Hz");
+        Ok(())
+    }
+
+    /// Sends `code` and returns the meaningful part of the response.
+    fn command(&mut self, mut code: u32, arg: u32) -> Result<u32, EmmcError> {
+        if code & CMD_NEED_APP != 0 {
+            let app_code = if self.rca != 0 {
+                CMD_APP_CMD | CMD_RSPNS_48
+            } else {
+                CMD_APP_CMD
+            };
+            let response = self.command(app_code, self.rca)?;
+            if self.rca != 0 && response == 0 {
+                return Err(EmmcError::Error);
+            }
+            code &= !CMD_NEED_APP;
+        }
+        self.wait_status(SR_CMD_INHIBIT)?;
+        self.registers.INTERRUPT.set(self.registers.INTERRUPT.get());
+        self.registers.ARG1.set(arg);
+        self.registers.CMDTM.set(code);
+        if code == CMD_SEND_OP_COND & !CMD_NEED_APP {
+            wait_ms(1000);
+        } else if code == CMD_SEND_IF_COND || code == CMD_APP_CMD {
+            wait_ms(100);
+        }
+        self.wait_interrupt(INT_CMD_DONE)?;
+
+        let response = self.registers.RESP0.get();
+        match code {
+            CMD_GO_IDLE | CMD_APP_CMD => Ok(0),
+            x if x == CMD_APP_CMD | CMD_RSPNS_48 => Ok(response & SR_APP_CMD),
+            x if x == CMD_SEND_OP_COND & !CMD_NEED_APP => Ok(response),
+            CMD_SEND_IF_COND if response == arg => Ok(0),
+            CMD_SEND_IF_COND => Err(EmmcError::Error),
+            CMD_ALL_SEND_CID => Ok(response
+                | self.registers.RESP1.get()
+                | self.registers.RESP2.get()
+                | self.registers.RESP3.get()),
+            CMD_SEND_REL_ADDR => {
+                let errors = ((response & 0x1fff)
+                    | ((response & 0x2000) << 6)
+                    | ((response & 0x4000) << 8)
+                    | ((response & 0x8000) << 8))
+                    & CMD_ERRORS_MASK;
+                if errors != 0 {
+                    return Err(EmmcError::Error);
+                }
+                Ok(response & CMD_RCA_MASK)
+            }
+            _ => Ok(response & CMD_ERRORS_MASK),
+        }
+    }
+
+    // waits until none of the `mask` status bits are set.
+    fn wait_status(&self, mask: u32) -> Result<(), EmmcError> {
+        self.wait_for(500, |r| {
+            r.STATUS.get() & mask == 0 || r.INTERRUPT.get() & INT_ERROR_MASK != 0
+        })?;
+        if self.registers.INTERRUPT.get() & INT_ERROR_MASK != 0 {
+            return Err(EmmcError::Error);
+        }
+        Ok(())
+    }
+
+    // waits for one of the `mask` interrupts, then acknowledges it.
+    fn wait_interrupt(&self, mask: u32) -> Result<(), EmmcError> {
+        let done = self.wait_for(1000, |r| r.INTERRUPT.get() & (mask | INT_ERROR_MASK) != 0);
+        let interrupt = self.registers.INTERRUPT.get();
+        if done.is_err() || interrupt & (INT_CMD_TIMEOUT | INT_DATA_TIMEOUT) != 0 {
+            self.registers.INTERRUPT.set(interrupt);
+            return Err(EmmcError::Timeout);
+        }
+        if interrupt & INT_ERROR_MASK != 0 {
+            self.registers.INTERRUPT.set(interrupt);
+            return Err(EmmcError::Error);
+        }
+        self.registers.INTERRUPT.set(mask);
+        Ok(())
+    }
+
+    // polls `condition` every millisecond, for at most `timeout_ms`.
+    fn wait_for(
+        &self,
+        timeout_ms: u64,
+        condition: impl Fn(&EmmcRegisterBlock) -> bool,
+    ) -> Result<(), EmmcError> {
+        for _ in 0..timeout_ms {
+            if condition(&self.registers) {
+                return Ok(());
+            }
+            wait_ms(1);
+        }
+        if condition(&self.registers) {
+            Ok(())
+        } else {
+            Err(EmmcError::Timeout)
+        }
+    }
+}
@@ -1,6 +1,6 @@
 use crate::mailbox::set_virtual_framebuffer_offset;
 use crate::PL011_UART;
-use space_invaders::{Color, FrameBufferInterface, KeyPressedKeys, UserInput};
+use space_invaders::{ClipRect, Color, FrameBufferInterface, KeyPressedKeys, UserInput};
 
 /// RPI 3 framebuffer
 pub struct FrameBuffer {
@@ -11,21 +11,28 @@ pub struct FrameBuffer {
     pub pitch: u32,
     pub is_rgb: bool,
     pub is_brg: bool,
-    /// crate::mailbox::FB_VIRTUAL_WIDTH
+    /// Width of the virtual buffer, the same as `width`
     pub fb_virtual_width: u32,
     /// Bits used by each pixel
     pub depth_bits: u32,
     pub current_index: u8,
+    pub clip: ClipRect,
 }
 
 impl UserInput for FrameBuffer {
     fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys> {
-        UARTIterator::new()
+        // the chars received since the last call, buffered by the UART IRQ.
+        core::iter::from_fn(|| PL011_UART.read_char_unblocking())
             .filter_map(|ch| match ch {
                 'a' | 'A' => Some(KeyPressedKeys::Left),
                 'd' | 'D' => Some(KeyPressedKeys::Right),
                 'r' | 'R' => Some(KeyPressedKeys::Restart),
                 ' ' => Some(KeyPressedKeys::Shoot),
+                // the UART turns carriage returns into newlines.
+                '\n' => Some(KeyPressedKeys::Start),
+                'p' | 'P' => Some(KeyPressedKeys::Pause),
+                'f' | 'F' => Some(KeyPressedKeys::Debug),
+                'q' | 'Q' => Some(KeyPressedKeys::Quit),
                 _ => None,
             })
             .into_iter()
@@ -34,23 +41,51 @@ impl UserInput for FrameBuffer {
 
 impl FrameBufferInterface for FrameBuffer {
     fn raw_buffer(&mut self) -> &mut [u32] {
-        let start = self.width() * self.current_height_offset();
+        let start = self.stride() * self.current_height_offset();
         let end_of_buffer = start + self.single_screen_len();
         &mut self.framebuff[start..end_of_buffer]
     }
 
-    fn width(&self) -> usize {
-        self.width as usize
+    fn width_u32(&self) -> u32 {
+        self.width
+    }
+
+    fn height_u32(&self) -> u32 {
+        self.height
+    }
+
+    fn stride(&self) -> usize {
+        (self.pitch / 4) as usize
     }
 
     fn use_pixel(&mut self, x_usize: usize, y_usize: usize, color: Color) {
-        let width = self.width();
-        let slice_ptr = (&mut self.raw_buffer()[width * y_usize + x_usize..]).as_mut_ptr();
+        if x_usize >= self.width() || y_usize >= self.height() {
+            return;
+        }
+        let stride = self.stride();
+        let slice_ptr = (&mut self.raw_buffer()[stride * y_usize + x_usize..]).as_mut_ptr();
         unsafe {
             core::ptr::write_volatile(slice_ptr, color.rgb());
         }
     }
 
+    fn read_pixel(&mut self, x_usize: usize, y_usize: usize) -> u32 {
+        if x_usize >= self.width() || y_usize >= self.height() {
+            return 0;
+        }
+        let stride = self.stride();
+        let slice_ptr = self.raw_buffer()[stride * y_usize + x_usize..].as_ptr();
+        unsafe { core::ptr::read_volatile(slice_ptr) }
+    }
+
+    fn clip(&self) -> ClipRect {
+        self.clip
+    }
+
+    fn set_clip(&mut self, clip: ClipRect) {
+        self.clip = clip;
+    }
+
     fn clear_screen(&mut self) {
         let slice_ptr = (&mut self.raw_buffer()).as_mut_ptr();
         for i in 0..self.single_screen_len() {
@@ -60,6 +95,10 @@ impl FrameBufferInterface for FrameBuffer {
         }
     }
 
+    fn buffer_count(&self) -> usize {
+        2
+    }
+
     fn update(&mut self) {
         set_virtual_framebuffer_offset(self.current_index as u32 * self.height);
         self.current_index = Self::inverse(self.current_index);
@@ -68,7 +107,7 @@ impl FrameBufferInterface for FrameBuffer {
 
 impl FrameBuffer {
     fn single_screen_len(&self) -> usize {
-        (self.height * self.width) as usize
+        self.height as usize * self.stride()
     }
     fn current_height_offset(&self) -> usize {
         self.height as usize * self.current_index as usize
@@ -81,34 +120,3 @@ impl FrameBuffer {
         }
     }
 }
-
-// Define a custom iterator that reads characters from UART until None is encountered.
-struct UARTIterator {
-    max_input: usize,
-}
-
-impl UARTIterator {
-    fn new() -> Self {
-        UARTIterator { max_input: 10 }
-    }
-}
-
-impl Iterator for UARTIterator {
-    type Item = char;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.max_input == 0 {
-            return None;
-        }
-        match PL011_UART.read_char_unblocking() {
-            Some(ch) => {
-                self.max_input -= 1;
-                Some(ch)
-            }
-            None => {
-                self.max_input = 0;
-                None
-            }
-        }
-    }
-}
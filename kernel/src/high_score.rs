@@ -0,0 +1,130 @@
+use crate::emmc::{Emmc, BLOCK_SIZE};
+use log::{error, info, warn};
+use space_invaders::{
+    HighScoreStore, HighScoreTable, MemoryHighScoreStore, HIGH_SCORES_MAGIC, HIGH_SCORES_MAX_BYTES,
+};
+
+/// The high scores go in the last sector before the usual 1MiB aligned first partition.
+/// Raspberry Pi OS images start their first partition way after it.
+const HIGH_SCORES_LBA: u32 = 2047;
+
+const MBR_SIGNATURE: [u8; 2] = [0x55, 0xaa];
+const MBR_SIGNATURE_OFFSET: usize = 510;
+const MBR_PARTITIONS_OFFSET: usize = 446;
+const MBR_PARTITION_LEN: usize = 16;
+const MBR_PARTITION_TYPE_OFFSET: usize = 4;
+const MBR_PARTITION_START_OFFSET: usize = 8;
+// a GPT protective MBR: the sectors right after it are taken.
+const MBR_TYPE_GPT: u8 = 0xee;
+
+/// Keeps the high scores in a sector of the SD card left free by the partition table.
+/// If the card can't be used, the scores are only kept until power off.
+pub struct SdCardHighScoreStore {
+    card: Option<Emmc>,
+    fallback: MemoryHighScoreStore,
+}
+
+impl SdCardHighScoreStore {
+    /// # Safety
+    ///
+    /// - Only one instance must exist, see `Emmc::new`.
+    pub unsafe fn new() -> Self {
+        let mut card = Emmc::new();
+        let card = match card.init() {
+            Ok(()) if is_sector_free(&mut card) && can_overwrite_sector(&mut card) => Some(card),
+            Ok(()) => {
+                warn!("No free sector for the high scores on the SD card");
+                None
+            }
+            Err(e) => {
+                error!("Failed to init the SD card: {:?}", e);
+                None
+            }
+        };
+        if card.is_none() {
+            warn!("High scores will be lost on power off");
+        }
+        Self {
+            card,
+            fallback: MemoryHighScoreStore::new(),
+        }
+    }
+}
+
+/// Only writes where the partition table says there's nothing.
+fn is_sector_free(card: &mut Emmc) -> bool {
+    let mut mbr = [0; BLOCK_SIZE];
+    if let Err(e) = card.read_block(0, &mut mbr) {
+        error!("Failed to read the MBR: {:?}", e);
+        return false;
+    }
+    if mbr[MBR_SIGNATURE_OFFSET..MBR_SIGNATURE_OFFSET + 2] != MBR_SIGNATURE {
+        return false;
+    }
+    let mut first_partition_start = u32::MAX;
+    for partition in
+        mbr[MBR_PARTITIONS_OFFSET..MBR_SIGNATURE_OFFSET].chunks_exact(MBR_PARTITION_LEN)
+    {
+        let partition_type = partition[MBR_PARTITION_TYPE_OFFSET];
+        if partition_type == MBR_TYPE_GPT {
+            return false;
+        }
+        let start = &partition[MBR_PARTITION_START_OFFSET..MBR_PARTITION_START_OFFSET + 4];
+        let start = u32::from_le_bytes([start[0], start[1], start[2], start[3]]);
+        if partition_type != 0 {
+            first_partition_start = first_partition_start.min(start);
+        }
+    }
+    info!("First partition starts at sector {}", first_partition_start);
+    HIGH_SCORES_LBA < first_partition_start
+}
+
+/// Only overwrites a sector never written, or with high scores already: something else could
+/// have been put before the first partition, e.g. by a bootloader.
+fn can_overwrite_sector(card: &mut Emmc) -> bool {
+    let mut sector = [0; BLOCK_SIZE];
+    if let Err(e) = card.read_block(HIGH_SCORES_LBA, &mut sector) {
+        error!("Failed to read the high scores sector: {:?}", e);
+        return false;
+    }
+    let unused = sector.iter().all(|byte| *byte == 0);
+    if !unused && !sector.starts_with(&HIGH_SCORES_MAGIC) {
+        warn!(
+            "Sector {} holds something else, not writing the high scores there",
+            HIGH_SCORES_LBA
+        );
+        return false;
+    }
+    true
+}
+
+impl HighScoreStore for SdCardHighScoreStore {
+    fn load(&mut self) -> HighScoreTable {
+        let Some(card) = &mut self.card else {
+            return self.fallback.load();
+        };
+        let mut sector = [0; BLOCK_SIZE];
+        if let Err(e) = card.read_block(HIGH_SCORES_LBA, &mut sector) {
+            error!("Failed to read the high scores: {:?}", e);
+            return HighScoreTable::new();
+        }
+        HighScoreTable::from_bytes(&sector).unwrap_or_else(|e| {
+            // e.g. it was never written.
+            info!("No high scores on the SD card: {:?}", e);
+            HighScoreTable::new()
+        })
+    }
+
+    fn save(&mut self, table: &HighScoreTable) {
+        let Some(card) = &mut self.card else {
+            return self.fallback.save(table);
+        };
+        let mut bytes = [0; HIGH_SCORES_MAX_BYTES];
+        let len = table.to_bytes(&mut bytes);
+        let mut sector = [0; BLOCK_SIZE];
+        sector[..len].copy_from_slice(&bytes[..len]);
+        if let Err(e) = card.write_block(HIGH_SCORES_LBA, &sector) {
+            error!("Failed to write the high scores: {:?}", e);
+        }
+    }
+}
@@ -0,0 +1,153 @@
+//! The BCM2837 interrupt controller, for the peripherals' IRQs.
+//!
+//! https://tc.gts3.org/cs3210/2020/spring/r/BCM2837-ARM-Peripherals.pdf cap 7 - pag 109
+
+use crate::mmio::INTERRUPT_CONTROLLER_START;
+use crate::println;
+use crate::uart_pl011::{MMIODerefWrapper, MutexTrait, NullLock};
+use cortex_a::asm;
+use cortex_a::registers::DAIF;
+use tock_registers::interfaces::{ReadWriteable, Readable, Writeable};
+use tock_registers::register_structs;
+use tock_registers::registers::{ReadOnly, WriteOnly};
+
+/// IRQs 0 to 31 are in the "1" registers, 32 to 63 in the "2" ones.
+const IRQ_COUNT: usize = 64;
+
+register_structs! {
+    #[allow(non_snake_case)]
+    RegisterBlock {
+        (0x00 => _basic_pending),
+        (0x04 => PENDING_1: ReadOnly<u32>),
+        (0x08 => PENDING_2: ReadOnly<u32>),
+        (0x0c => _fiq_control),
+        // writing a 1 enables the IRQ, 0s are ignored.
+        (0x10 => ENABLE_1: WriteOnly<u32>),
+        (0x14 => ENABLE_2: WriteOnly<u32>),
+        (0x18 => _enable_basic),
+        // writing a 1 disables the IRQ, 0s are ignored.
+        (0x1c => DISABLE_1: WriteOnly<u32>),
+        (0x20 => DISABLE_2: WriteOnly<u32>),
+        (0x24 => @END),
+    }
+}
+
+type Registers = MMIODerefWrapper<RegisterBlock>;
+
+/// The peripheral IRQs handled, see the IRQ table in the datasheet.
+#[derive(Debug, Copy, Clone)]
+pub enum PeripheralIrq {
+    /// System timer compare 1, 0 and 2 are used by the GPU.
+    SystemTimer1 = 1,
+    Uart = 57,
+}
+
+/// Called in the IRQ exception, with IRQs masked: it must not block.
+pub type IrqHandler = fn();
+
+pub struct InterruptController {
+    registers: Registers,
+    handlers: NullLock<[Option<IrqHandler>; IRQ_COUNT]>,
+}
+
+impl InterruptController {
+    /// # Safety
+    ///
+    /// - The user must ensure to provide a correct MMIO start address.
+    const unsafe fn new(mmio_start_addr: usize) -> Self {
+        Self {
+            registers: Registers::new(mmio_start_addr),
+            handlers: NullLock::new([None; IRQ_COUNT]),
+        }
+    }
+
+    /// Calls `handler` whenever `irq` is raised, from now on.
+    ///
+    /// # Safety
+    ///
+    /// - IRQs must be masked: the handlers are not locked.
+    pub unsafe fn register(
+        &self,
+        irq: PeripheralIrq,
+        handler: IrqHandler,
+    ) -> Result<(), &'static str> {
+        let irq = irq as usize;
+        (&self.handlers).lock(|handlers| {
+            if handlers[irq].is_some() {
+                return Err("IRQ handler already registered");
+            }
+            handlers[irq] = Some(handler);
+            Ok(())
+        })?;
+        let (enable, bit) = if irq < 32 {
+            (&self.registers.ENABLE_1, irq)
+        } else {
+            (&self.registers.ENABLE_2, irq - 32)
+        };
+        enable.set(1 << bit);
+        Ok(())
+    }
+
+    /// Calls the handlers of the IRQs pending. One without handler is disabled, or it would be
+    /// raised again right away.
+    pub fn handle_pending(&self) {
+        let pending = [
+            (self.registers.PENDING_1.get(), &self.registers.DISABLE_1, 0),
+            (
+                self.registers.PENDING_2.get(),
+                &self.registers.DISABLE_2,
+                32,
+            ),
+        ];
+        for (mut bits, disable, first_irq) in pending {
+            while bits != 0 {
+                let bit = bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                let handler = (&self.handlers).lock(|handlers| handlers[first_irq + bit]);
+                match handler {
+                    Some(handler) => handler(),
+                    None => {
+                        println!("Unhandled IRQ {}, disabling it.", first_irq + bit);
+                        disable.set(1 << bit);
+                    }
+                }
+            }
+        }
+    }
+}
+
+pub static INTERRUPT_CONTROLLER: InterruptController =
+    unsafe { InterruptController::new(INTERRUPT_CONTROLLER_START) };
+
+/// Lets the core take IRQs, `_start_rust` masks them.
+///
+/// # Safety
+///
+/// - The exception vectors must be installed, see `boot::exception::init`.
+pub unsafe fn unmask_irqs() {
+    DAIF.modify(DAIF::I::Unmasked);
+}
+
+/// Sleeps until `done`, checked after every IRQ.
+pub fn wait_until(done: impl Fn() -> bool) {
+    loop {
+        // masked while checking: an IRQ taken between the check and `wfi` would be missed. `wfi`
+        // still wakes up on it, and it's taken once unmasked.
+        DAIF.modify(DAIF::I::Masked);
+        if done() {
+            DAIF.modify(DAIF::I::Unmasked);
+            return;
+        }
+        asm::wfi();
+        DAIF.modify(DAIF::I::Unmasked);
+    }
+}
+
+/// Runs `f` with IRQs masked, e.g. to use what an IRQ handler could use too.
+pub fn without_irqs<R>(f: impl FnOnce() -> R) -> R {
+    let daif = DAIF.get();
+    DAIF.modify(DAIF::I::Masked);
+    let ret = f();
+    DAIF.set(daif);
+    ret
+}
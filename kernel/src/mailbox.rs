@@ -1,32 +1,30 @@
 use crate::framebuffer::FrameBuffer;
 use crate::mailbox::ReqResp::ResponseSuccessful;
 use crate::mmio::VIDEOCORE_MBOX_BASE;
+use crate::mmu::clean_invalidate_dcache;
 use crate::{debug, error};
 
 use core::mem;
 use core::ops::BitAnd;
 use cortex_a::asm;
 use log::info;
-use space_invaders::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use space_invaders::{ClipRect, SCREEN_HEIGHT, SCREEN_WIDTH};
 use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::registers::{ReadOnly, WriteOnly};
 
 const LFB_MESSAGE_SIZE: usize = 35;
 /// Set physical (display) width/height
 const FB_PHYSICAL_WH_TAG: u32 = 0x00048003;
-/// Width of the requested frame buffer
-const FB_PHYSICAL_WIDTH: u32 = SCREEN_WIDTH;
-/// Height of the requested frame buffer
-const FB_PHYSICAL_HEIGHT: u32 = SCREEN_HEIGHT;
-
-pub const FB_BUFFER_LEN: usize = FB_PHYSICAL_HEIGHT as usize * FB_PHYSICAL_WIDTH as usize;
+/// Get physical (display) width/height, as negotiated with the display
+const GET_FB_PHYSICAL_WH_TAG: u32 = 0x00040003;
 
 /// Set virtual (buffer) width/height
 const FB_VIRTUAL_WH_TAG: u32 = 0x00048004;
-const FB_VIRTUAL_WIDTH: u32 = SCREEN_WIDTH;
-const FB_VIRTUAL_HEIGHT: u32 = SCREEN_HEIGHT * 2;
 
-pub const TOTAL_FB_BUFFER_LEN: usize = FB_VIRTUAL_HEIGHT as usize * FB_VIRTUAL_WIDTH as usize;
+/// The smallest screen the playfield is scaled to, smaller displays get this anyway.
+const MIN_SCREEN_SIZE: (u32, u32) = (640, 480);
+/// The largest screen the playfield is scaled to, larger displays get this.
+const MAX_SCREEN_SIZE: (u32, u32) = (1920, 1080);
 
 const FB_VIRTUAL_OFFSET_TAG: u32 = 0x48009;
 const FB_VIRTUAL_OFFSET_X: u32 = 0;
@@ -159,7 +157,41 @@ pub fn query_board_serial() -> Option<u64> {
     };
 }
 
-const fn lfb_message() -> Message<LFB_MESSAGE_SIZE> {
+const GET_PHYSICAL_SIZE_MESSAGE_SIZE: usize = 8;
+const fn get_physical_size_message() -> Message<GET_PHYSICAL_SIZE_MESSAGE_SIZE> {
+    let mut ret = [0u32; GET_PHYSICAL_SIZE_MESSAGE_SIZE];
+    ret[0] = (GET_PHYSICAL_SIZE_MESSAGE_SIZE * mem::size_of::<u32>()) as u32;
+    ret[1] = MBOX_REQUEST;
+    ret[2] = GET_FB_PHYSICAL_WH_TAG;
+    ret[3] = 8; // value buffer size in bytes
+    ret[4] = 0; // :b 31 clear: request, | b31 set: response b30-b0: value length in bytes
+    ret[5] = 0; // width, used by the response.
+    ret[6] = 0; // height, used by the response.
+    ret[7] = LAST_TAG;
+    Message(ret)
+}
+
+/// The size of the screen to ask for: the display's own, if it's not too small or too big.
+fn screen_size() -> (u32, u32) {
+    let message = get_physical_size_message();
+    let (width, height) = if send_message_sync(Channel::PROP, &message) {
+        (message.0[5], message.0[6])
+    } else {
+        (0, 0)
+    };
+    info!("Display size: {}x{}", width, height);
+    if width == 0 || height == 0 {
+        // e.g. no display attached yet.
+        return (SCREEN_WIDTH, SCREEN_HEIGHT);
+    }
+    (
+        width.clamp(MIN_SCREEN_SIZE.0, MAX_SCREEN_SIZE.0),
+        height.clamp(MIN_SCREEN_SIZE.1, MAX_SCREEN_SIZE.1),
+    )
+}
+
+/// `virtual_height` is twice `height`, for double buffering.
+const fn lfb_message(width: u32, height: u32) -> Message<LFB_MESSAGE_SIZE> {
     let mut ret = [0u32; LFB_MESSAGE_SIZE];
     ret[0] = (LFB_MESSAGE_SIZE * mem::size_of::<u32>()) as u32;
     ret[1] = MBOX_REQUEST;
@@ -168,18 +200,18 @@ const fn lfb_message() -> Message<LFB_MESSAGE_SIZE> {
     ret[3] = 8;
     ret[4] = 8;
     //FrameBufferInfo.width
-    ret[5] = FB_PHYSICAL_WIDTH;
+    ret[5] = width;
     //FrameBufferInfo.height
-    ret[6] = FB_PHYSICAL_HEIGHT;
+    ret[6] = height;
 
     //set virt wh
     ret[7] = FB_VIRTUAL_WH_TAG;
     ret[8] = 8;
     ret[9] = 8;
     //FrameBufferInfo.virtual_width
-    ret[10] = FB_VIRTUAL_WIDTH;
+    ret[10] = width;
     //FrameBufferInfo.virtual_height
-    ret[11] = FB_VIRTUAL_HEIGHT;
+    ret[11] = height * 2;
 
     // set virt offset
     ret[12] = FB_VIRTUAL_OFFSET_TAG;
@@ -214,7 +246,8 @@ const fn lfb_message() -> Message<LFB_MESSAGE_SIZE> {
 }
 
 pub fn lfb_init<'a: 'static>(tentative: usize) -> Option<FrameBuffer> {
-    let message = lfb_message();
+    let (width, height) = screen_size();
+    let message = lfb_message(width, height);
     let res = send_message_sync(Channel::PROP, &message);
     return if res && message.0[28] != 0 {
         //convert GPU address to ARM address
@@ -231,10 +264,14 @@ pub fn lfb_init<'a: 'static>(tentative: usize) -> Option<FrameBuffer> {
         //get the actual channel order. brg = 0, rgb > 0
         let is_rgb = message.0[24] != 0;
 
+        let virtual_width = message.0[10];
+        let virtual_height = message.0[11];
+        // the rows can be longer than the width.
+        let buffer_len = (pitch / 4 * virtual_height) as usize;
+
         let casted = fb_ptr_raw as *const u32 as *mut u32;
         let casted = unsafe { &mut *casted };
-        let framebuff: &mut [u32] =
-            unsafe { core::slice::from_raw_parts_mut(casted, TOTAL_FB_BUFFER_LEN) };
+        let framebuff: &mut [u32] = unsafe { core::slice::from_raw_parts_mut(casted, buffer_len) };
         let fb = FrameBuffer {
             framebuff,
             width,
@@ -243,8 +280,9 @@ pub fn lfb_init<'a: 'static>(tentative: usize) -> Option<FrameBuffer> {
             depth_bits: depth,
             is_rgb,
             is_brg: !is_rgb,
-            fb_virtual_width: FB_VIRTUAL_WIDTH,
+            fb_virtual_width: virtual_width,
             current_index: 0,
+            clip: ClipRect::FULL,
         };
         info!(
             "All good, setting up the frame buffer now: {}, height: {}, pitch: {}, depth:{}, is_rgb: {}",
@@ -438,6 +476,8 @@ fn send_message_sync<const T: usize>(channel: Channel, message: &Message<T>) ->
         nop();
     }
 
+    // the GPU reads and writes the message in RAM, not in the cache.
+    clean_invalidate_dcache(raw_ptr_addr, mem::size_of::<Message<T>>());
     raw_mailbox.write_address(final_addr);
 
     /* now wait for the response */
@@ -452,6 +492,7 @@ fn send_message_sync<const T: usize>(channel: Channel, message: &Message<T>) ->
         }
 
         if raw_mailbox.get_read() == final_addr as u32 {
+            clean_invalidate_dcache(raw_ptr_addr, mem::size_of::<Message<T>>());
             return match message.response_status() {
                 ReqResp::Request => {
                     debug!("message stll contains a request ?!");
@@ -7,24 +7,38 @@
 #![feature(asm_const)]
 #![feature(return_position_impl_trait_in_trait)]
 
+extern crate alloc;
+
 use crate::logger::IrisLogger;
 use core::panic::PanicInfo;
 use cortex_a::asm;
+#[cfg(feature = "no_mmu")]
 use cortex_a::registers::SCTLR_EL1;
 
+mod allocator;
 mod boot;
+mod emmc;
 mod framebuffer;
+mod high_score;
+mod interrupt;
 mod logger;
 mod mailbox;
+#[cfg_attr(feature = "no_mmu", allow(dead_code))]
+mod mmu;
 mod print;
+mod ring_buffer;
 mod time;
 mod uart_pl011;
 
+use crate::high_score::SdCardHighScoreStore;
+use crate::interrupt::{PeripheralIrq, INTERRUPT_CONTROLLER};
 use crate::mailbox::{max_clock_speed, set_clock_speed};
 use crate::mmio::PL011_UART_START;
 use crate::time::TIME_MANAGER;
 use crate::uart_pl011::PL011Uart;
 use log::{debug, error, info};
+use space_invaders::TimeManagerInterface;
+#[cfg(feature = "no_mmu")]
 use tock_registers::interfaces::ReadWriteable;
 
 static IRIS_LOGGER: IrisLogger = IrisLogger::new();
@@ -35,19 +49,35 @@ mod mmio {
     pub const UART_OFFSET: usize = 0x0020_1000;
     pub const VIDEOCORE_MBOX_OFFSET: usize = 0x0000_B880;
     pub const TIME_OFFSET: usize = 0x0000_3000;
+    pub const GPIO_OFFSET: usize = 0x0020_0000;
+    pub const EMMC_OFFSET: usize = 0x0030_0000;
+    pub const INTERRUPT_CONTROLLER_OFFSET: usize = 0x0000_B200;
     pub const TIMER_REG_BASE: usize = IO_BASE + TIME_OFFSET;
     pub const PL011_UART_START: usize = IO_BASE + UART_OFFSET;
     pub const VIDEOCORE_MBOX_BASE: usize = IO_BASE + VIDEOCORE_MBOX_OFFSET;
+    pub const GPIO_START: usize = IO_BASE + GPIO_OFFSET;
+    pub const EMMC_START: usize = IO_BASE + EMMC_OFFSET;
+    pub const INTERRUPT_CONTROLLER_START: usize = IO_BASE + INTERRUPT_CONTROLLER_OFFSET;
 }
 
 #[inline]
 unsafe fn kernel_init() -> ! {
+    boot::exception::init();
+    #[cfg(not(feature = "no_mmu"))]
+    mmu::init();
+    // `mmu::init` enables the caches along with the MMU.
+    #[cfg(feature = "no_mmu")]
     SCTLR_EL1.modify(SCTLR_EL1::C::Cacheable + SCTLR_EL1::I::Cacheable);
+    allocator::init();
     unsafe {
         PL011_UART.init().unwrap();
     }
     println!("kernel_init");
+    // to check the exception report, e.g. with `make qemu_exception`.
+    #[cfg(feature = "exception_test")]
+    core::arch::asm!("brk #0");
     IRIS_LOGGER.init().unwrap();
+    init_interrupts();
     let max_clock_speed = max_clock_speed();
     info!("Kernel speed: {:?}", max_clock_speed);
     set_clock_speed(max_clock_speed.unwrap());
@@ -55,11 +85,51 @@ unsafe fn kernel_init() -> ! {
     panic!()
 }
 
+/// The keys pressed are received by the UART IRQ, see `FrameBuffer::get_input`. The game loop
+/// waits for the timer IRQ between frames, see `BcmGpuTimer::wait_for_tick`.
+unsafe fn init_interrupts() {
+    INTERRUPT_CONTROLLER
+        .register(PeripheralIrq::Uart, || PL011_UART.handle_interrupt())
+        .unwrap();
+    INTERRUPT_CONTROLLER
+        .register(PeripheralIrq::SystemTimer1, || {
+            TIME_MANAGER.handle_interrupt()
+        })
+        .unwrap();
+    PL011_UART.enable_rx_interrupt();
+    interrupt::unmask_irqs();
+}
+
 fn main() {
     info!("main");
     let fb = mailbox::lfb_init(0).expect("Failed to init framebuffer");
+    // the GPU reads it from RAM: cached, a frame would only show up once evicted.
+    #[cfg(not(feature = "no_mmu"))]
+    unsafe {
+        mmu::map_non_cacheable(
+            fb.framebuff.as_ptr() as usize,
+            core::mem::size_of_val(fb.framebuff),
+        )
+    };
+    // the boot time jitters by a few microseconds, good enough as a seed.
+    let seed = TIME_MANAGER.now().as_micros() as u64;
+    let high_scores = unsafe { SdCardHighScoreStore::new() };
     println!("Starting game...");
-    space_invaders::run_game(fb, &TIME_MANAGER);
+    space_invaders::run_game(fb, &TIME_MANAGER, seed, high_scores);
+    info!("Quit, halting.");
+    loop {
+        asm::wfe()
+    }
+}
+
+/// The heap is out of a block big enough, see `allocator`.
+#[alloc_error_handler]
+fn alloc_error(layout: core::alloc::Layout) -> ! {
+    panic!(
+        "Allocation of {} bytes, aligned to {}, failed",
+        layout.size(),
+        layout.align()
+    );
 }
 
 #[panic_handler]
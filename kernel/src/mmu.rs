@@ -0,0 +1,155 @@
+//! The MMU, with an identity mapping: RAM is normal cacheable memory, the peripherals are device
+//! memory. Without it every access is a device one, even with the caches enabled in `SCTLR_EL1`.
+//!
+//! 4KiB granule and 32 bits of address: a level 1 table of 1GiB blocks, the first one split in
+//! 2MiB blocks by a level 2 table.
+
+use crate::mmio::IO_BASE;
+use crate::uart_pl011::{MutexTrait, NullLock};
+use core::arch::asm;
+use cortex_a::asm::barrier;
+use cortex_a::registers::{MAIR_EL1, SCTLR_EL1, TCR_EL1, TTBR0_EL1};
+use tock_registers::interfaces::{ReadWriteable, Writeable};
+
+const ENTRIES: usize = 512;
+const BLOCK_1GIB: usize = 1 << 30;
+const BLOCK_2MIB: usize = 1 << 21;
+/// The Cortex-A53's data cache line.
+const CACHE_LINE: usize = 64;
+
+// stage 1 descriptor bits.
+const VALID: u64 = 1 << 0;
+// a table, a block otherwise.
+const TABLE: u64 = 1 << 1;
+const ATTR_INDEX_SHIFT: u64 = 2;
+const INNER_SHAREABLE: u64 = 0b11 << 8;
+const ACCESS_FLAG: u64 = 1 << 10;
+const PRIVILEGED_EXECUTE_NEVER: u64 = 1 << 53;
+const UNPRIVILEGED_EXECUTE_NEVER: u64 = 1 << 54;
+
+/// The attribute indexes in `MAIR_EL1`, see `init`.
+#[derive(Copy, Clone)]
+enum MemoryKind {
+    Device = 0,
+    Normal = 1,
+    /// Normal memory that's not cached: writes still get merged, e.g. for the framebuffer.
+    NonCacheable = 2,
+}
+
+impl MemoryKind {
+    fn block(self, address: usize) -> u64 {
+        let attributes = match self {
+            // nothing is fetched from there, not even speculatively.
+            MemoryKind::Device | MemoryKind::NonCacheable => {
+                PRIVILEGED_EXECUTE_NEVER | UNPRIVILEGED_EXECUTE_NEVER
+            }
+            MemoryKind::Normal => INNER_SHAREABLE,
+        };
+        address as u64 | (self as u64) << ATTR_INDEX_SHIFT | attributes | ACCESS_FLAG | VALID
+    }
+}
+
+#[repr(C, align(4096))]
+struct Table([u64; ENTRIES]);
+
+struct TranslationTables {
+    /// Only the first 4 entries are used, 4GiB in all.
+    level_1: Table,
+    /// The first GiB: RAM and the peripherals.
+    level_2: Table,
+}
+
+static TABLES: NullLock<TranslationTables> = NullLock::new(TranslationTables {
+    level_1: Table([0; ENTRIES]),
+    level_2: Table([0; ENTRIES]),
+});
+
+/// Maps everything below `IO_BASE` as RAM, from there to 2GiB as peripherals, and turns on the
+/// MMU and the caches.
+///
+/// # Safety
+///
+/// - Only once, before anything is cached.
+pub unsafe fn init() {
+    (&TABLES).lock(|tables| {
+        for (i, entry) in tables.level_2.0.iter_mut().enumerate() {
+            let address = i * BLOCK_2MIB;
+            let kind = if address < IO_BASE {
+                MemoryKind::Normal
+            } else {
+                MemoryKind::Device
+            };
+            *entry = kind.block(address);
+        }
+        tables.level_1.0[0] = tables.level_2.0.as_ptr() as u64 | TABLE | VALID;
+        // the ARM local peripherals.
+        tables.level_1.0[1] = MemoryKind::Device.block(BLOCK_1GIB);
+        TTBR0_EL1.set_baddr(tables.level_1.0.as_ptr() as u64);
+    });
+
+    MAIR_EL1.write(
+        MAIR_EL1::Attr0_Device::nonGathering_nonReordering_EarlyWriteAck
+            + MAIR_EL1::Attr1_Normal_Outer::WriteBack_NonTransient_ReadWriteAlloc
+            + MAIR_EL1::Attr1_Normal_Inner::WriteBack_NonTransient_ReadWriteAlloc
+            + MAIR_EL1::Attr2_Normal_Outer::NonCacheable
+            + MAIR_EL1::Attr2_Normal_Inner::NonCacheable,
+    );
+    TCR_EL1.write(
+        TCR_EL1::IPS::Bits_32
+            + TCR_EL1::TG0::KiB_4
+            + TCR_EL1::SH0::Inner
+            + TCR_EL1::ORGN0::WriteBack_ReadAlloc_WriteAlloc_Cacheable
+            + TCR_EL1::IRGN0::WriteBack_ReadAlloc_WriteAlloc_Cacheable
+            + TCR_EL1::EPD0::EnableTTBR0Walks
+            + TCR_EL1::A1::TTBR0
+            + TCR_EL1::T0SZ.val(32)
+            + TCR_EL1::EPD1::DisableTTBR1Walks,
+    );
+    invalidate_tlb();
+
+    SCTLR_EL1.modify(SCTLR_EL1::M::Enable + SCTLR_EL1::C::Cacheable + SCTLR_EL1::I::Cacheable);
+    barrier::isb(barrier::SY);
+}
+
+/// Maps the 2MiB blocks holding `start..start + len` as normal non-cacheable memory: what's
+/// written goes to RAM right away, for the GPU to read.
+///
+/// # Safety
+///
+/// - The kernel must not use anything else in those blocks, it wouldn't be cached anymore.
+pub unsafe fn map_non_cacheable(start: usize, len: usize) {
+    let blocks = start / BLOCK_2MIB..(start + len).div_ceil(BLOCK_2MIB);
+    (&TABLES).lock(|tables| {
+        // break before make: the TLB must never hold both mappings.
+        for entry in &mut tables.level_2.0[blocks.clone()] {
+            *entry = 0;
+        }
+        invalidate_tlb();
+        for i in blocks.clone() {
+            tables.level_2.0[i] = MemoryKind::NonCacheable.block(i * BLOCK_2MIB);
+        }
+        invalidate_tlb();
+    });
+    // lines cached while it was mapped as RAM, e.g. prefetched.
+    clean_invalidate_dcache(blocks.start * BLOCK_2MIB, blocks.len() * BLOCK_2MIB);
+}
+
+/// Writes the cached lines of `start..start + len` to RAM and drops them, for memory shared with
+/// the GPU: it sees what was written, and what it writes is read from RAM afterwards.
+pub fn clean_invalidate_dcache(start: usize, len: usize) {
+    let end = start + len;
+    let mut line = start & !(CACHE_LINE - 1);
+    while line < end {
+        unsafe { asm!("dc civac, {}", in(reg) line) };
+        line += CACHE_LINE;
+    }
+    barrier::dsb(barrier::SY);
+}
+
+/// After the tables were changed.
+fn invalidate_tlb() {
+    barrier::dsb(barrier::ISHST);
+    unsafe { asm!("tlbi vmalle1") };
+    barrier::dsb(barrier::ISH);
+    barrier::isb(barrier::SY);
+}
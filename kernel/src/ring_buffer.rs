@@ -0,0 +1,52 @@
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+/// A FIFO of bytes between an interrupt handler, pushing, and the code it interrupts, popping.
+/// It holds `N - 1` bytes at most: one slot is kept empty to tell a full buffer from an empty one.
+///
+/// Only one side pushes and only one side pops, so no lock is needed. It uses only atomic loads
+/// and stores: without the MMU all memory is Device memory, where exclusive accesses may fault.
+pub struct RingBuffer<const N: usize> {
+    data: UnsafeCell<[u8; N]>,
+    // the next slot written, only moved by `push`.
+    head: AtomicUsize,
+    // the next slot read, only moved by `pop`.
+    tail: AtomicUsize,
+}
+
+unsafe impl<const N: usize> Sync for RingBuffer<N> {}
+
+impl<const N: usize> RingBuffer<N> {
+    pub const fn new() -> Self {
+        Self {
+            data: UnsafeCell::new([0; N]),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+        }
+    }
+
+    /// Adds `byte` at the end, false if the buffer is full: it's dropped.
+    pub fn push(&self, byte: u8) -> bool {
+        let head = self.head.load(Ordering::Relaxed);
+        let next = (head + 1) % N;
+        if next == self.tail.load(Ordering::Acquire) {
+            return false;
+        }
+        // `pop` doesn't read this slot until `head` is moved past it.
+        unsafe { (*self.data.get())[head] = byte };
+        self.head.store(next, Ordering::Release);
+        true
+    }
+
+    /// Removes the oldest byte.
+    pub fn pop(&self) -> Option<u8> {
+        let tail = self.tail.load(Ordering::Relaxed);
+        if tail == self.head.load(Ordering::Acquire) {
+            return None;
+        }
+        // `push` doesn't write this slot until `tail` is moved past it.
+        let byte = unsafe { (*self.data.get())[tail] };
+        self.tail.store((tail + 1) % N, Ordering::Release);
+        Some(byte)
+    }
+}
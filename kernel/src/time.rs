@@ -1,8 +1,10 @@
+use crate::interrupt;
 use crate::mmio::TIMER_REG_BASE;
+use core::sync::atomic::{AtomicU32, Ordering};
 use core::time::Duration;
-use tock_registers::interfaces::Readable;
+use tock_registers::interfaces::{Readable, Writeable};
 use tock_registers::register_bitfields;
-use tock_registers::registers::ReadOnly;
+use tock_registers::registers::{ReadOnly, ReadWrite};
 register_bitfields! {
     u32,
 
@@ -11,33 +13,88 @@ register_bitfields! {
 #[repr(C)]
 /// https://tc.gts3.org/cs3210/2020/spring/r/BCM2837-ARM-Peripherals.pdf cap 12 - pag 172
 struct ArmTimeRegisters {
-    /// CS register
-    _controller_status: ReadOnly<u32>,
+    /// CS register: bit n is set when compare n matched, writing a 1 clears it.
+    controller_status: ReadWrite<u32>,
     /// CLO: counter lower 32 bits
     counter_lower: ReadOnly<u32>,
     /// CHI: System Timer Counter Higher 32 bits
     counter_higher: ReadOnly<u32>,
-    /// system Timer compare registers - 4 in total
-    _compare: [u32; 4],
+    /// system Timer compare registers - 4 in total, each raises its IRQ when it matches CLO.
+    compare: [ReadWrite<u32>; 4],
 }
 
+/// The compare register raising the ticks, see `PeripheralIrq::SystemTimer1`.
+const TICK_CHANNEL: usize = 1;
+
 /// BCM's system timer.
 /// Note the GPU uses timers 0 and 2, so they're reserved.
-pub struct BcmGpuTimer;
+pub struct BcmGpuTimer {
+    /// In microseconds, 0 until the first `wait_for_tick`.
+    tick_period: AtomicU32,
+    /// Moved by `handle_interrupt` only.
+    ticks: AtomicU32,
+    /// The tick the last `wait_for_tick` returned on.
+    ticks_waited: AtomicU32,
+}
 impl BcmGpuTimer {
     pub const fn new() -> Self {
-        Self
+        Self {
+            tick_period: AtomicU32::new(0),
+            ticks: AtomicU32::new(0),
+            ticks_waited: AtomicU32::new(0),
+        }
+    }
+
+    fn registers(&self) -> &ArmTimeRegisters {
+        unsafe { &*(TIMER_REG_BASE as *const ArmTimeRegisters) }
+    }
+
+    /// A tick passed: the next one is set up a period after it. Ticks missed are skipped.
+    pub(crate) fn handle_interrupt(&self) {
+        let registers = self.registers();
+        registers.controller_status.set(1 << TICK_CHANNEL);
+        let period = self.tick_period.load(Ordering::Relaxed);
+        if period == 0 {
+            // the compare register matched before any tick was asked for.
+            return;
+        }
+        let now = registers.counter_lower.get();
+        let mut next = registers.compare[TICK_CHANNEL].get().wrapping_add(period);
+        // CLO wraps around, so it's compared by the distance to `next`.
+        if next.wrapping_sub(now) > period {
+            next = now.wrapping_add(period);
+        }
+        registers.compare[TICK_CHANNEL].set(next);
+        // only the IRQ writes it: no atomic add, see `RingBuffer`.
+        let ticks = self.ticks.load(Ordering::Relaxed);
+        self.ticks.store(ticks.wrapping_add(1), Ordering::Release);
     }
 }
 impl space_invaders::TimeManagerInterface for BcmGpuTimer {
     fn now(&self) -> Duration {
-        let ptr = TIMER_REG_BASE as *mut ArmTimeRegisters;
-        let registers = unsafe { &mut *ptr };
+        let registers = self.registers();
         let lower = registers.counter_lower.get() as u64;
         let upper = registers.counter_higher.get() as u64;
         let microseconds = (upper << 32) | lower;
         Duration::from_micros(microseconds)
     }
+
+    /// Sleeps until the next timer IRQ ticking every `period`, the first call starts them.
+    /// Doesn't wait if one passed since the last call already.
+    fn wait_for_tick(&self, _last_tick: Duration, period: Duration) {
+        let period = u32::try_from(period.as_micros()).expect("Conversion failed");
+        if self.tick_period.load(Ordering::Relaxed) != period {
+            let registers = self.registers();
+            self.tick_period.store(period, Ordering::Relaxed);
+            self.ticks_waited
+                .store(self.ticks.load(Ordering::Acquire), Ordering::Relaxed);
+            registers.compare[TICK_CHANNEL].set(registers.counter_lower.get().wrapping_add(period));
+        }
+        let waited = self.ticks_waited.load(Ordering::Relaxed);
+        interrupt::wait_until(|| self.ticks.load(Ordering::Acquire) != waited);
+        self.ticks_waited
+            .store(self.ticks.load(Ordering::Acquire), Ordering::Relaxed);
+    }
 }
 
 pub static TIME_MANAGER: BcmGpuTimer = BcmGpuTimer::new();
@@ -2,6 +2,8 @@
 //
 // Copyright (c) 2021-2022 Andre Richter <andre.o.richter@gmail.com>
 
+use crate::interrupt;
+use crate::ring_buffer::RingBuffer;
 use core::cell::UnsafeCell;
 use core::marker::PhantomData;
 use core::{fmt, ops};
@@ -186,6 +188,24 @@ register_bitfields! {
         ]
     ],
 
+    /// Interrupt Mask Set/Clear Register.
+    IMSC [
+        /// Receive timeout interrupt mask. A read returns the current mask for the UARTRTINTR
+        /// interrupt. On a write of 1, the mask of the interrupt is set. A write of 0 clears the
+        /// mask.
+        RTIM OFFSET(6) NUMBITS(1) [
+            Disabled = 0,
+            Enabled = 1
+        ],
+
+        /// Receive interrupt mask. A read returns the current mask for the UARTRXINTR interrupt. On
+        /// a write of 1, the mask of the interrupt is set. A write of 0 clears the mask.
+        RXIM OFFSET(4) NUMBITS(1) [
+            Disabled = 0,
+            Enabled = 1
+        ]
+    ],
+
     /// Interrupt Clear Register.
     ICR [
         /// Meta field for all pending interrupts.
@@ -205,6 +225,8 @@ register_structs! {
         (0x2c => LCR_H: WriteOnly<u32, LCR_H::Register>),
         (0x30 => CR: WriteOnly<u32, CR::Register>),
         (0x34 => _reserved3),
+        (0x38 => IMSC: ReadWrite<u32, IMSC::Register>),
+        (0x3c => _reserved4),
         (0x44 => ICR: WriteOnly<u32, ICR::Register>),
         (0x48 => @END),
     }
@@ -229,9 +251,13 @@ struct PL011UartInner {
 // Public Definitions
 //--------------------------------------------------------------------------------------------------
 
+/// The chars received and not read yet, see `PL011Uart::handle_interrupt`.
+const RX_BUFFER_SIZE: usize = 64;
+
 /// Representation of the UART.
 pub struct PL011Uart {
     inner: NullLock<PL011UartInner>,
+    rx_buffer: RingBuffer<RX_BUFFER_SIZE>,
 }
 
 //--------------------------------------------------------------------------------------------------
@@ -390,13 +416,20 @@ impl PL011Uart {
     pub const unsafe fn new(mmio_start_addr: usize) -> Self {
         Self {
             inner: NullLock::new(PL011UartInner::new(mmio_start_addr)),
+            rx_buffer: RingBuffer::new(),
         }
     }
 }
 
 impl PL011Uart {
+    /// Locks `inner` with IRQs masked: `handle_interrupt` locks it too, and the `NullLock` doesn't
+    /// keep it from running in the middle of a print.
+    fn lock_inner<R>(&self, f: impl FnOnce(&mut PL011UartInner) -> R) -> R {
+        interrupt::without_irqs(|| (&self.inner).lock(f))
+    }
+
     pub(crate) unsafe fn init(&self) -> Result<(), &'static str> {
-        (&self.inner).lock(|inner| inner.init());
+        self.lock_inner(|inner| inner.init());
 
         Ok(())
     }
@@ -404,31 +437,63 @@ impl PL011Uart {
     /// Passthrough of `args` to the `core::fmt::Write` implementation, but guarded by a Mutex to
     /// serialize access.
     pub fn write_char(&self, c: char) {
-        (&self.inner).lock(|inner| inner.write_char(c));
+        self.lock_inner(|inner| inner.write_char(c));
     }
 
     pub(crate) fn write_fmt(&self, args: core::fmt::Arguments) -> fmt::Result {
         // Fully qualified syntax for the call to `core::fmt::Write::write_fmt()` to increase
         // readability.
-        (&self.inner).lock(|inner| fmt::Write::write_fmt(inner, args))
+        self.lock_inner(|inner| fmt::Write::write_fmt(inner, args))
     }
 
     pub(crate) fn flush(&self) {
         // Spin until TX FIFO empty is set.
-        (&self.inner).lock(|inner| inner.flush());
+        self.lock_inner(|inner| inner.flush());
     }
 
+    /// Waits for a char received by `handle_interrupt`.
     pub(crate) fn read_char(&self) -> char {
-        (&self.inner).lock(|inner| inner.read_char_converting(BlockingMode::Blocking).unwrap())
+        loop {
+            if let Some(c) = self.read_char_unblocking() {
+                return c;
+            }
+            asm::nop();
+        }
     }
+
+    /// The oldest char received by `handle_interrupt`, if any.
     pub(crate) fn read_char_unblocking(&self) -> Option<char> {
-        (&self.inner).lock(|inner| inner.read_char_converting(BlockingMode::NonBlocking))
+        self.rx_buffer.pop().map(char::from)
+    }
+
+    /// Raises the UART IRQ when chars are received, or stop coming with some still in the RX FIFO.
+    /// `handle_interrupt` must be registered for it.
+    pub(crate) fn enable_rx_interrupt(&self) {
+        self.lock_inner(|inner| {
+            inner
+                .registers
+                .IMSC
+                .write(IMSC::RXIM::Enabled + IMSC::RTIM::Enabled)
+        });
+    }
+
+    /// Moves the RX FIFO to the buffer `read_char_unblocking` reads from. Chars are dropped when
+    /// it's full.
+    pub(crate) fn handle_interrupt(&self) {
+        (&self.inner).lock(|inner| {
+            while let Some(c) = inner.read_char_converting(BlockingMode::NonBlocking) {
+                // the UART only receives bytes.
+                self.rx_buffer.push(c as u8);
+            }
+            // emptying the RX FIFO clears the RX interrupts already, this clears the others.
+            inner.registers.ICR.write(ICR::ALL::SET);
+        });
     }
 
     fn clear_rx(&self) {
         // Read from the RX FIFO until it is indicating empty.
-        while (&self.inner)
-            .lock(|inner| inner.read_char_converting(BlockingMode::NonBlocking))
+        while self
+            .lock_inner(|inner| inner.read_char_converting(BlockingMode::NonBlocking))
             .is_some()
         {}
     }
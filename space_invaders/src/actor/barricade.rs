@@ -5,6 +5,7 @@ use crate::framebuffer::color::SHOT_COLOR;
 use crate::framebuffer::fb_trait::FrameBufferInterface;
 use crate::framebuffer::{Color, Coordinates};
 use crate::{SCREEN_MARGIN, SCREEN_WIDTH};
+use core::ops::Range;
 
 const BARRICADE_BOX_WIDTH: u32 = SHOOT_BOX_WIDTH + 10;
 const BARRICADE_BOX_HEIGHT: u32 = SHOOT_BOX_HEIGHT + 10;
@@ -15,24 +16,44 @@ const BARRICADE_BOX_COLOR: Color = SHOT_COLOR;
 const BARRICADE_OFFSET_FROM_HERO_Y: f64 = SHOOT_SPAWN_OFFSET_Y as f64 + 10.0;
 // these are derived from the pattern in create_barricade.
 const TOTAL_BLOCKS_PER_BARRICADE: usize = 14;
-const BARRICADE_ROWS: f64 = 3.0;
+const BARRICADE_ROWS: u32 = 3;
 const BARRICADE_COLS: u32 = 6;
 
-const BARRICADE_OFFSET_Y: f64 = HERO_SPAWN_Y as f64
-    - BARRICADE_OFFSET_FROM_HERO_Y
-    - BARRICADE_ROWS * BARRICADE_BOX_HEIGHT as f64;
+pub const BARRICADE_WIDTH: u32 = BARRICADE_BOX_WIDTH * BARRICADE_COLS;
+pub const BARRICADE_HEIGHT: u32 = BARRICADE_BOX_HEIGHT * BARRICADE_ROWS;
+// every row of pixels is a `u128`, see `Barricade::row_mask`.
+const _: () = assert!(BARRICADE_WIDTH <= u128::BITS);
 
-const TOTAL_BARRICADES: usize = 4;
+const BARRICADE_OFFSET_Y: f64 =
+    HERO_SPAWN_Y as f64 - BARRICADE_OFFSET_FROM_HERO_Y - BARRICADE_HEIGHT as f64;
+
+pub const TOTAL_BARRICADES: usize = 4;
 // screen margin * 4 to add additional margin to the screen.
 // it's divided by TOTAL_BARRICATES + 1 because it's the count of space inbetween barricade.
 const BARRICADE_OFFSET_X: f64 =
-    (SCREEN_WIDTH - SCREEN_MARGIN * 4 - BARRICADE_BOX_WIDTH * BARRICADE_COLS) as f64
-        / (TOTAL_BARRICADES + 1) as f64;
+    (SCREEN_WIDTH - SCREEN_MARGIN * 4 - BARRICADE_WIDTH) as f64 / (TOTAL_BARRICADES + 1) as f64;
+
+// the hole left by a shot, bit 0 is the leftmost pixel.
+// it's symmetric, so it looks the same as written here.
+const CRATER_WIDTH: u32 = 12;
+#[rustfmt::skip]
+const CRATER: [u16; 8] = [
+    0b0010_0110_0100,
+    0b0101_1111_1010,
+    0b0011_1111_1100,
+    0b0111_1111_1110,
+    0b0111_1111_1110,
+    0b0011_1111_1100,
+    0b0101_1111_1010,
+    0b0010_0110_0100,
+];
 
-// This is really a barricade block.
+/// A shelter, worn down pixel by pixel.
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub struct Barricade {
     pub(crate) structure: ActorStructure,
+    // one row per line, bit x is set if the pixel x of that line is still there.
+    pixels: [u128; BARRICADE_HEIGHT as usize],
 }
 
 impl Actor for Barricade {
@@ -45,12 +66,30 @@ impl Actor for Barricade {
     }
 
     fn draw(&self, fb: &mut impl FrameBufferInterface) {
-        fb.draw_rect_fill(
-            self.get_coordinates(),
-            self.structure.width,
-            self.structure.height,
-            BARRICADE_BOX_COLOR,
-        );
+        let (x0, y0) = (self.get_coordinates().x(), self.get_coordinates().y());
+        // one rect per run of pixels still standing.
+        for (y, row) in (0..).zip(self.pixels.iter()) {
+            let (mut row, mut x) = (*row, 0);
+            while row != 0 {
+                let gap = row.trailing_zeros();
+                row >>= gap;
+                x += gap;
+                let run = row.trailing_ones();
+                fb.draw_rect_fill(
+                    &Coordinates::new(x0 + x, y0 + y),
+                    run,
+                    1,
+                    BARRICADE_BOX_COLOR,
+                );
+                row >>= run;
+                x += run;
+            }
+        }
+    }
+
+    /// Only the pixels still standing can be hit.
+    fn is_hit(&self, actor_structure: &ActorStructure) -> bool {
+        self.hit_point(actor_structure, false).is_some()
     }
 }
 
@@ -59,35 +98,32 @@ impl Barricade {
     pub fn new(coordinates: Coordinates) -> Self {
         Barricade {
             structure: Self::structure(coordinates),
+            pixels: Self::create_barricade(),
         }
     }
 
     const fn structure(coordinates: Coordinates) -> ActorStructure {
         ActorStructure {
             sprite: None,
-            width: BARRICADE_BOX_WIDTH,
-            height: BARRICADE_BOX_HEIGHT,
+            width: BARRICADE_WIDTH,
+            height: BARRICADE_HEIGHT,
             alive: true,
             coordinates,
         }
     }
 
-    // todo: probably these could be const functions.
-    pub fn create_barricades() -> [Barricade; TOTAL_BLOCKS_PER_BARRICADE * TOTAL_BARRICADES] {
-        let mut barricades =
-            [Barricade::new(Coordinates::new(0, 0)); TOTAL_BLOCKS_PER_BARRICADE * TOTAL_BARRICADES];
-        for i in 1..=TOTAL_BARRICADES {
-            let x = SCREEN_MARGIN + BARRICADE_OFFSET_X as u32 * i as u32;
-            let coordinate = Coordinates::new(x, BARRICADE_OFFSET_Y as u32);
-            let new_b = Self::create_barricade(coordinate);
-            barricades[(i - 1) * TOTAL_BLOCKS_PER_BARRICADE
-                ..(i - 1) * TOTAL_BLOCKS_PER_BARRICADE + TOTAL_BLOCKS_PER_BARRICADE]
-                .copy_from_slice(&new_b);
-        }
-        barricades
+    /// # Panics
+    /// If the barricades don't fit on the screen.
+    #[must_use]
+    pub fn create_barricades() -> [Barricade; TOTAL_BARRICADES] {
+        core::array::from_fn(|i| {
+            let i = u32::try_from(i + 1).expect("Conversion failed");
+            let x = SCREEN_MARGIN + BARRICADE_OFFSET_X as u32 * i;
+            Barricade::new(Coordinates::new(x, BARRICADE_OFFSET_Y as u32))
+        })
     }
 
-    fn create_barricade(coordinates: Coordinates) -> [Barricade; TOTAL_BLOCKS_PER_BARRICADE] {
+    fn create_barricade() -> [u128; BARRICADE_HEIGHT as usize] {
         /*
         shape:
             xxxx
@@ -96,24 +132,159 @@ impl Barricade {
           keep in sync with BARRICADE_ROWS const.
         */
         #[rustfmt::skip]
-        const OFFSETS: [(f64, f64); TOTAL_BLOCKS_PER_BARRICADE] = [
-                                                    (1.0, 0.0), (2.0, 0.0), (3.0, 0.0), (4.0, 0.0),
-                                        (0.0, 1.0), (1.0, 1.0), (2.0, 1.0), (3.0, 1.0), (4.0, 1.0), (5.0, 1.0),
-                                        (0.0, 2.0), (1.0, 2.0),                         (4.0, 2.0), (5.0, 2.0),
+        const OFFSETS: [(u32, u32); TOTAL_BLOCKS_PER_BARRICADE] = [
+                            (1, 0), (2, 0), (3, 0), (4, 0),
+                    (0, 1), (1, 1), (2, 1), (3, 1), (4, 1), (5, 1),
+                    (0, 2), (1, 2),                 (4, 2), (5, 2),
         ];
-        let mut barricades = [Barricade::new(coordinates); TOTAL_BLOCKS_PER_BARRICADE];
-
-        for (index, offset) in OFFSETS.iter().enumerate() {
-            barricades[index]
-                .structure
-                .coordinates
-                .add_virtual_x(offset.0 * BARRICADE_BOX_WIDTH as f64);
-            barricades[index]
-                .structure
-                .coordinates
-                .add_virtual_y(offset.1 * BARRICADE_BOX_HEIGHT as f64, 1);
+        let mut pixels = [0; BARRICADE_HEIGHT as usize];
+        for (col, row) in OFFSETS {
+            let block =
+                Self::row_mask(&(col * BARRICADE_BOX_WIDTH..(col + 1) * BARRICADE_BOX_WIDTH));
+            let lines = row * BARRICADE_BOX_HEIGHT..(row + 1) * BARRICADE_BOX_HEIGHT;
+            for line in &mut pixels[lines.start as usize..lines.end as usize] {
+                *line |= block;
+            }
+        }
+        pixels
+    }
+
+    // the bits of the pixels in `xs`.
+    fn row_mask(xs: &Range<u32>) -> u128 {
+        // shifting by 128 would overflow: all the bits, or none.
+        u128::MAX
+            .checked_shr(u128::BITS - (xs.end - xs.start))
+            .unwrap_or(0)
+            << xs.start
+    }
+
+    // the part of the barricade covered by `structure`, relative to the barricade.
+    fn overlap(&self, structure: &ActorStructure) -> Option<(Range<u32>, Range<u32>)> {
+        let (x0, y0) = (self.get_coordinates().x(), self.get_coordinates().y());
+        let (x, y) = (structure.coordinates.x(), structure.coordinates.y());
+        let xs = x.max(x0)..(x + structure.width).min(x0 + BARRICADE_WIDTH);
+        let ys = y.max(y0)..(y + structure.height).min(y0 + BARRICADE_HEIGHT);
+        if xs.is_empty() || ys.is_empty() {
+            return None;
+        }
+        Some((xs.start - x0..xs.end - x0, ys.start - y0..ys.end - y0))
+    }
+
+    /// Where `structure` touches what's left of the barricade, on the line it reaches first:
+    /// the lowest one when coming from below, the highest one otherwise.
+    pub(crate) fn hit_point(
+        &self,
+        structure: &ActorStructure,
+        from_below: bool,
+    ) -> Option<Coordinates> {
+        let (xs, mut ys) = self.overlap(structure)?;
+        let mask = Self::row_mask(&xs);
+        let is_hit = |y: &u32| self.pixels[*y as usize] & mask != 0;
+        let y = if from_below {
+            ys.rev().find(is_hit)
+        } else {
+            ys.find(is_hit)
+        }?;
+        Some(Coordinates::new(
+            self.get_coordinates().x() + xs.start + (xs.end - xs.start) / 2,
+            self.get_coordinates().y() + y,
+        ))
+    }
+
+    /// Blows a crater in the barricade, centered on `point`.
+    pub(crate) fn erode(&mut self, point: &Coordinates) {
+        let left = i64::from(point.x())
+            - i64::from(self.get_coordinates().x())
+            - i64::from(CRATER_WIDTH / 2);
+        let top = i64::from(point.y())
+            - i64::from(self.get_coordinates().y())
+            - i64::try_from(CRATER.len() / 2).expect("Conversion failed");
+        for (dy, crater_row) in (0..).zip(CRATER) {
+            let Some(line) = usize::try_from(top + dy)
+                .ok()
+                .and_then(|y| self.pixels.get_mut(y))
+            else {
+                continue;
+            };
+            let crater_row = u128::from(crater_row);
+            let hole = if left >= 0 {
+                crater_row.checked_shl(u32::try_from(left).expect("Conversion failed"))
+            } else {
+                crater_row.checked_shr(u32::try_from(-left).expect("Conversion failed"))
+            };
+            *line &= !hole.unwrap_or(0);
+        }
+        self.update_alive();
+    }
+
+    /// Removes every pixel covered by `structure`, e.g. an enemy walking through it.
+    pub(crate) fn erase(&mut self, structure: &ActorStructure) {
+        let Some((xs, ys)) = self.overlap(structure) else {
+            return;
+        };
+        let mask = Self::row_mask(&xs);
+        for line in &mut self.pixels[ys.start as usize..ys.end as usize] {
+            *line &= !mask;
         }
+        self.update_alive();
+    }
+
+    fn update_alive(&mut self) {
+        self.structure.alive = self.pixels.iter().any(|line| *line != 0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Barricade, BARRICADE_HEIGHT, BARRICADE_WIDTH};
+    use crate::actor::{Actor, ActorStructure, Shoot, ShootOwner, SHOOT_BOX_WIDTH};
+    use crate::framebuffer::Coordinates;
+
+    #[test]
+    fn test_shots_carve_through() {
+        let mut barricade = Barricade::new(Coordinates::new(100, 100));
+        let x = 100 + BARRICADE_WIDTH / 2;
+        let mut hits = 0;
+        // shots keep coming from below, in the middle of the barricade.
+        for y in (90..100 + BARRICADE_HEIGHT).rev().step_by(2) {
+            let shoot = Shoot::new(Coordinates::new(x, y), ShootOwner::Hero);
+            if let Some(point) = barricade.hit_point(&shoot.structure, true) {
+                barricade.erode(&point);
+                hits += 1;
+            }
+        }
+        assert!(hits > 1);
+        // there's a hole now, but the sides are still there.
+        let mut column = ActorStructure::new(Coordinates::new(x, 100));
+        column.width = SHOOT_BOX_WIDTH;
+        column.height = BARRICADE_HEIGHT;
+        assert!(!barricade.is_hit(&column));
+        column.coordinates = Coordinates::new(102, 100);
+        assert!(barricade.is_hit(&column));
+    }
+
+    #[test]
+    fn test_only_standing_pixels_are_hit() {
+        let barricade = Barricade::new(Coordinates::new(0, 0));
+        // the top left corner is empty, see the shape.
+        let corner = ActorStructure {
+            width: 2,
+            height: 2,
+            ..ActorStructure::new(Coordinates::new(0, 0))
+        };
+        assert!(!barricade.is_hit(&corner));
+    }
 
-        barricades
+    #[test]
+    fn test_erase() {
+        let mut barricade = Barricade::new(Coordinates::new(10, 10));
+        let mut enemy = ActorStructure::new(Coordinates::new(0, 0));
+        enemy.width = BARRICADE_WIDTH + 20;
+        enemy.height = BARRICADE_HEIGHT / 2;
+        barricade.erase(&enemy);
+        assert!(barricade.is_alive());
+        enemy.height = BARRICADE_HEIGHT + 20;
+        barricade.erase(&enemy);
+        assert!(!barricade.is_alive());
     }
 }
@@ -0,0 +1,207 @@
+use crate::actor::ActorStructure;
+use crate::framebuffer::color::{SHOT_COLOR, WHITE_COLOR};
+use crate::framebuffer::fb_trait::FrameBufferInterface;
+use crate::framebuffer::{Color, Coordinates};
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// How many effects can play at the same time, the oldest one makes room for a new one.
+pub const MAX_EFFECTS: usize = 16;
+
+/// How long the game stands still after the hero got shot.
+pub const HERO_DEATH_MS: u64 = 1000;
+
+const PARTICLE_SIZE: u32 = 4;
+// the directions the particles fly to.
+const PARTICLE_DIRECTIONS: [(f64, f64); 8] = [
+    (1.0, 0.0),
+    (0.707, 0.707),
+    (0.0, 1.0),
+    (-0.707, 0.707),
+    (-1.0, 0.0),
+    (-0.707, -0.707),
+    (0.0, -1.0),
+    (0.707, -0.707),
+];
+
+// same as the hero sprite.
+const HERO_COLOR: Color = Color::new(240, 224, 64);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EffectKind {
+    /// An enemy or the UFO got shot.
+    Explosion,
+    /// A shot hit a barricade.
+    Debris,
+    /// The hero got shot.
+    HeroDeath,
+}
+
+impl EffectKind {
+    const fn duration_ms(self) -> u64 {
+        match self {
+            EffectKind::Explosion => 300,
+            EffectKind::Debris => 200,
+            EffectKind::HeroDeath => HERO_DEATH_MS,
+        }
+    }
+
+    // how far the particles fly, in pixels.
+    const fn radius(self) -> f64 {
+        match self {
+            EffectKind::Explosion => 30.0,
+            EffectKind::Debris => 15.0,
+            EffectKind::HeroDeath => 60.0,
+        }
+    }
+
+    const fn color(self) -> Color {
+        match self {
+            EffectKind::Explosion => WHITE_COLOR,
+            EffectKind::Debris => SHOT_COLOR,
+            EffectKind::HeroDeath => HERO_COLOR,
+        }
+    }
+}
+
+/// A burst of particles flying away from where something got hit.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Effect {
+    kind: EffectKind,
+    center: Coordinates,
+    elapsed_ms: u64,
+    alive: bool,
+}
+
+impl Effect {
+    const fn new() -> Self {
+        Self {
+            kind: EffectKind::Explosion,
+            center: Coordinates::new(0, 0),
+            elapsed_ms: 0,
+            alive: false,
+        }
+    }
+
+    fn draw(&self, fb: &mut impl FrameBufferInterface) {
+        // elapsed_ms is never above the duration, a few hundred milliseconds.
+        #[allow(clippy::cast_precision_loss)]
+        let distance = self.kind.radius() * self.elapsed_ms as f64 / self.kind.duration_ms() as f64;
+        let half_particle = f64::from(PARTICLE_SIZE) / 2.0;
+        for (dx, dy) in PARTICLE_DIRECTIONS {
+            let x = self.center.virtual_x + dx * distance - half_particle;
+            let y = self.center.virtual_y + dy * distance - half_particle;
+            let out_of_screen = x < 0.0
+                || y < 0.0
+                || x + f64::from(PARTICLE_SIZE) >= f64::from(SCREEN_WIDTH)
+                || y + f64::from(PARTICLE_SIZE) >= f64::from(SCREEN_HEIGHT);
+            if out_of_screen {
+                continue;
+            }
+            let particle = Coordinates {
+                virtual_x: x,
+                virtual_y: y,
+            };
+            fb.draw_rect_fill(&particle, PARTICLE_SIZE, PARTICLE_SIZE, self.kind.color());
+        }
+    }
+}
+
+/// Fixed size pool of the effects currently playing.
+pub struct Effects {
+    effects: [Effect; MAX_EFFECTS],
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Effects {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            effects: [Effect::new(); MAX_EFFECTS],
+        }
+    }
+
+    /// Starts playing `kind` on the center of `structure`.
+    pub fn spawn(&mut self, kind: EffectKind, structure: &ActorStructure) {
+        let center = Coordinates {
+            virtual_x: structure.coordinates.virtual_x + f64::from(structure.width) / 2.0,
+            virtual_y: structure.coordinates.virtual_y + f64::from(structure.height) / 2.0,
+        };
+        // a free slot, otherwise the oldest effect.
+        if let Some(effect) = self
+            .effects
+            .iter_mut()
+            .max_by_key(|e| (!e.alive, e.elapsed_ms))
+        {
+            *effect = Effect {
+                kind,
+                center,
+                elapsed_ms: 0,
+                alive: true,
+            };
+        }
+    }
+
+    pub fn update(&mut self, delta_ms: u64) {
+        for effect in self.effects.iter_mut().filter(|e| e.alive) {
+            effect.elapsed_ms += delta_ms;
+            if effect.elapsed_ms >= effect.kind.duration_ms() {
+                effect.alive = false;
+            }
+        }
+    }
+
+    #[must_use]
+    pub fn playing(&self) -> usize {
+        self.effects.iter().filter(|e| e.alive).count()
+    }
+
+    pub fn draw(&self, fb: &mut impl FrameBufferInterface) {
+        for effect in self.effects.iter().filter(|e| e.alive) {
+            effect.draw(fb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{EffectKind, Effects, MAX_EFFECTS};
+    use crate::actor::ActorStructure;
+    use crate::framebuffer::Coordinates;
+
+    #[test]
+    fn test_effects_expire() {
+        let mut effects = Effects::new();
+        let structure = ActorStructure::new(Coordinates::new(10, 10));
+        effects.spawn(EffectKind::Debris, &structure);
+        effects.spawn(EffectKind::Explosion, &structure);
+        assert_eq!(effects.playing(), 2);
+        effects.update(EffectKind::Debris.duration_ms());
+        assert_eq!(effects.playing(), 1);
+        effects.update(EffectKind::Explosion.duration_ms());
+        assert_eq!(effects.playing(), 0);
+    }
+
+    #[test]
+    fn test_full_pool_replaces_the_oldest() {
+        let mut effects = Effects::new();
+        let structure = ActorStructure::new(Coordinates::new(10, 10));
+        effects.spawn(EffectKind::HeroDeath, &structure);
+        effects.update(10);
+        for _ in 1..MAX_EFFECTS {
+            effects.spawn(EffectKind::Explosion, &structure);
+        }
+        assert_eq!(effects.playing(), MAX_EFFECTS);
+
+        effects.spawn(EffectKind::Explosion, &structure);
+        assert_eq!(effects.playing(), MAX_EFFECTS);
+        assert!(effects
+            .effects
+            .iter()
+            .all(|e| e.kind == EffectKind::Explosion));
+    }
+}
@@ -1,7 +1,7 @@
-use crate::actor::{Actor, ActorStructure, Sprite, HERO_HEIGHT};
+use crate::actor::{Actor, ActorStructure, AnimatedSprite, SpriteFrame, HERO_HEIGHT, UFO_HEIGHT};
 use crate::framebuffer::fb_trait::FrameBufferInterface;
 use crate::framebuffer::Coordinates;
-use crate::{SCREEN_HEIGHT, SCREEN_MARGIN, SCREEN_WIDTH};
+use crate::{Level, SCREEN_HEIGHT, SCREEN_MARGIN, SCREEN_WIDTH};
 use log::info;
 
 const ENEMY_WIDTH: u32 = 40;
@@ -14,53 +14,86 @@ const ENEMY_ROWS: u32 = 4;
 pub const ENEMY_COLS: u32 = ((SCREEN_WIDTH - SCREEN_MARGIN * 2)
     / (ENEMY_WIDTH + BASE_OFFSET_IN_BETWEEN_ALIENS_IN_ROW))
     - 10;
-const ENEMY_OFFSET_Y_FROM_MARGIN: u32 = HERO_HEIGHT;
+// leave room for the ufo above the enemies.
+const ENEMY_OFFSET_Y_FROM_MARGIN: u32 = HERO_HEIGHT + UFO_HEIGHT + 10;
 
 /// by how many pixel should the enemy go down
 pub const ENEMY_STEP_DOWN: u32 = (SCREEN_HEIGHT - SCREEN_MARGIN) / ENEMY_HEIGHT;
 
+// in the first wave, see `Level`.
 const ENEMY_SPEED_PER_MS: f64 = 20.0 / 1000.0; // pixels per second
 
+// how far the enemies move before switching pose.
+const ENEMY_MARCH_STEP: f64 = 16.0;
+
+// the fewer of a kind, the more they are worth. Like in the arcade.
+pub const ENEMY_POINTS: u32 = 30;
+pub const GREEN_ENEMY_POINTS: u32 = 20;
+pub const ENEMY_RED_POINTS: u32 = 10;
+
 pub const TOTAL_ENEMIES: usize = (ENEMY_ROWS * ENEMY_COLS) as usize;
 
 static GREEN_ENEMY_SPRITE: &[u32] =
     crate::include_bytes_align_as!(u32, "../../../assets/green.data");
 static ENEMY_RED_SPRITE: &[u32] = crate::include_bytes_align_as!(u32, "../../../assets/red.data");
 pub static ENEMY_SPRITE: &[u32] = crate::include_bytes_align_as!(u32, "../../../assets/alien.data");
+// alien.data is the same image as yellow.data.
+static ENEMY_SPRITE_1: &[u32] =
+    crate::include_bytes_align_as!(u32, "../../../assets/yellow-1.data");
+static GREEN_ENEMY_SPRITE_1: &[u32] =
+    crate::include_bytes_align_as!(u32, "../../../assets/green-1.data");
+static ENEMY_RED_SPRITE_1: &[u32] =
+    crate::include_bytes_align_as!(u32, "../../../assets/red-1.data");
+
+// the two poses of every kind of alien.
+static ENEMY_FRAMES: [SpriteFrame; 2] = [
+    SpriteFrame::new(ENEMY_SPRITE, ENEMY_WIDTH, ENEMY_HEIGHT),
+    SpriteFrame::new(ENEMY_SPRITE_1, ENEMY_WIDTH, ENEMY_HEIGHT),
+];
+static GREEN_ENEMY_FRAMES: [SpriteFrame; 2] = [
+    SpriteFrame::new(GREEN_ENEMY_SPRITE, 40, 32),
+    SpriteFrame::new(GREEN_ENEMY_SPRITE_1, 40, 32),
+];
+static ENEMY_RED_FRAMES: [SpriteFrame; 2] = [
+    SpriteFrame::new(ENEMY_RED_SPRITE, 40, 32),
+    SpriteFrame::new(ENEMY_RED_SPRITE_1, 40, 32),
+];
 
 #[derive(Copy, Clone, Debug)]
 pub struct Enemy {
     pub(crate) structure: ActorStructure,
+    animation: AnimatedSprite,
+    /// what shooting it is worth.
+    pub(crate) points: u32,
     // every loop iteration, might cause a sub-pixel movement.
 }
 
 impl Enemy {
     fn new() -> Self {
-        Enemy {
-            structure: ActorStructure {
-                sprite: Some(Sprite::new(ENEMY_SPRITE)),
-                width: ENEMY_WIDTH,
-                height: ENEMY_HEIGHT,
-                alive: true,
-                coordinates: Coordinates::new(0, 0),
-            },
-        }
+        let mut enemy = Enemy {
+            structure: ActorStructure::new(Coordinates::new(0, 0)),
+            animation: AnimatedSprite::new(&ENEMY_FRAMES, 0),
+            points: ENEMY_POINTS,
+        };
+        enemy.animation.apply(&mut enemy.structure);
+        enemy
     }
 
     pub fn set_green_alien(&mut self) {
-        const ENEMY_GREEN_WIDTH: u32 = 40;
-        const ENEMY_GREEN_HEIGHT: u32 = 32;
-        self.structure.width = ENEMY_GREEN_WIDTH;
-        self.structure.height = ENEMY_GREEN_HEIGHT;
-        self.structure.sprite = Some(Sprite::new(GREEN_ENEMY_SPRITE));
+        self.animation = AnimatedSprite::new(&GREEN_ENEMY_FRAMES, 0);
+        self.animation.apply(&mut self.structure);
+        self.points = GREEN_ENEMY_POINTS;
     }
 
     pub fn set_red_alien(&mut self) {
-        const ENEMY_RED_WIDTH: u32 = 39;
-        const ENEMY_RED_HEIGHT: u32 = 31;
-        self.structure.width = ENEMY_RED_WIDTH;
-        self.structure.height = ENEMY_RED_HEIGHT;
-        self.structure.sprite = Some(Sprite::new(ENEMY_RED_SPRITE));
+        self.animation = AnimatedSprite::new(&ENEMY_RED_FRAMES, 0);
+        self.animation.apply(&mut self.structure);
+        self.points = ENEMY_RED_POINTS;
+    }
+
+    fn next_pose(&mut self) {
+        self.animation.next_frame();
+        self.animation.apply(&mut self.structure);
     }
 }
 
@@ -77,25 +110,34 @@ pub struct Enemies {
     pub(crate) enemies: [Enemy; TOTAL_ENEMIES],
     // used for speedup calculation and high score
     pub(crate) enemies_dead: usize,
+    /// earned by shooting enemies.
+    pub(crate) points: u32,
     lowest_col: (u32, u32),
     largest_col: (u32, u32),
     direction: EnemiesDirection,
+    speed_per_ms: f64,
+    // distance moved since the last pose change.
+    marched: f64,
 }
 impl Enemies {
     pub fn all_dead(&self) -> bool {
         TOTAL_ENEMIES - self.enemies_dead == 0
     }
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(level: Level) -> Self {
         Self {
-            enemies: Self::init_enemies(),
+            enemies: Self::init_enemies(level.enemies_offset_y()),
             lowest_col: (0, 0),
             largest_col: (0, 0),
             enemies_dead: 0,
+            points: 0,
             direction: EnemiesDirection::Right,
+            speed_per_ms: ENEMY_SPEED_PER_MS * level.enemy_speed_factor(),
+            marched: 0.0,
         }
     }
 
-    pub fn init_enemies() -> [Enemy; TOTAL_ENEMIES] {
+    /// `offset_y` moves the whole grid down.
+    pub fn init_enemies(offset_y: u32) -> [Enemy; TOTAL_ENEMIES] {
         let mut enemies = [Enemy::new(); TOTAL_ENEMIES];
 
         for x in 0..ENEMY_COLS {
@@ -106,7 +148,8 @@ impl Enemies {
                 let index = (y * ENEMY_COLS + x) as usize;
                 let offset_y = (ENEMY_HEIGHT + BASE_OFFSET_IN_BETWEEN_ALIENS_IN_COL) * y
                     + SCREEN_MARGIN
-                    + ENEMY_OFFSET_Y_FROM_MARGIN;
+                    + ENEMY_OFFSET_Y_FROM_MARGIN
+                    + offset_y;
 
                 enemies[index].structure.coordinates = Coordinates::new(offset_x, offset_y);
                 if y == 1 {
@@ -156,11 +199,19 @@ impl Enemies {
                 }
             }
             self.direction = self.direction.invert_direction();
+            self.next_pose();
             return;
         }
         // speed up per dead enemy
-        let speedup = (1.0 + self.enemies_dead as f64 * 0.20) * ENEMY_SPEED_PER_MS;
-        let offset_x = self.direction.to_offset(delta_ms, speedup);
+        let speedup = (1.0 + self.enemies_dead as f64 * 0.20) * self.speed_per_ms;
+        let offset_x = self
+            .direction
+            .to_offset(delta_ms, self.speed_per_ms + speedup);
+        self.marched += offset_x.abs();
+        if self.marched >= ENEMY_MARCH_STEP {
+            self.marched -= ENEMY_MARCH_STEP;
+            self.next_pose();
+        }
 
         for x in 0..ENEMY_COLS {
             for y in 0..ENEMY_ROWS {
@@ -181,6 +232,12 @@ impl Enemies {
             }
         }
     }
+    /// Like in the arcade, all the aliens change pose on every step of the march.
+    fn next_pose(&mut self) {
+        for enemy in &mut self.enemies {
+            enemy.next_pose();
+        }
+    }
     pub fn draw(&self, fb: &mut impl FrameBufferInterface) {
         for enemy in self.enemies.iter().filter(|e| e.is_alive()) {
             enemy.draw(fb);
@@ -201,13 +258,56 @@ impl EnemiesDirection {
             Left => Right,
         }
     }
-    fn to_offset(self, delta_ms: u64, speedup: f64) -> f64 {
+    fn to_offset(self, delta_ms: u64, speed_per_ms: f64) -> f64 {
         use EnemiesDirection::{Left, Right};
         let delta_ms = delta_ms as f64;
         let sign = match self {
             Right => 1.0,
             Left => -1.0,
         };
-        sign * (ENEMY_SPEED_PER_MS + speedup) * delta_ms
+        sign * speed_per_ms * delta_ms
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        Enemies, ENEMY_FRAMES, ENEMY_HEIGHT, ENEMY_MARCH_STEP, ENEMY_RED_FRAMES,
+        ENEMY_SPEED_PER_MS, ENEMY_WIDTH, GREEN_ENEMY_FRAMES,
+    };
+    use crate::Level;
+
+    #[test]
+    fn test_enemies_change_pose_while_marching() {
+        let mut enemies = Enemies::new(Level::default());
+        let first_pose = enemies.enemies[0].structure.sprite;
+        assert_eq!(first_pose, Some(ENEMY_FRAMES[0].sprite));
+
+        // base speed plus the speedup of a full grid.
+        let step_ms = 400;
+        assert!((ENEMY_SPEED_PER_MS * 2.0 * 400.0 - ENEMY_MARCH_STEP).abs() < f64::EPSILON);
+        enemies.move_enemies(step_ms + 1);
+        assert_eq!(
+            enemies.enemies[0].structure.sprite,
+            Some(ENEMY_FRAMES[1].sprite)
+        );
+        assert_eq!(enemies.enemies[0].structure.width, ENEMY_FRAMES[1].width);
+        enemies.move_enemies(step_ms + 1);
+        assert_eq!(enemies.enemies[0].structure.sprite, first_pose);
+    }
+
+    #[test]
+    fn test_poses_keep_the_hitbox() {
+        for frame in ENEMY_FRAMES
+            .iter()
+            .chain(&GREEN_ENEMY_FRAMES)
+            .chain(&ENEMY_RED_FRAMES)
+        {
+            assert_eq!((frame.width, frame.height), (ENEMY_WIDTH, ENEMY_HEIGHT));
+            assert_eq!(
+                frame.sprite.sprite.len(),
+                (ENEMY_WIDTH * ENEMY_HEIGHT) as usize
+            );
+        }
     }
 }
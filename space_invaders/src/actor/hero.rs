@@ -19,6 +19,8 @@ pub enum HeroMovementDirection {
     Right,
     Still,
     RestartGame,
+    PauseGame,
+    QuitGame,
 }
 
 #[derive(Copy, Clone)]
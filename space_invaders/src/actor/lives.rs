@@ -4,6 +4,8 @@ use crate::{Coordinates, FrameBufferInterface, SCREEN_MARGIN};
 const UI_LIFES_X: u32 = SCREEN_MARGIN / 2;
 const UI_LIFES_Y: u32 = SCREEN_MARGIN / 2;
 const UI_LIFES_X_OFFSET_BETWEEN_LIFES: u32 = 20;
+// more wouldn't fit next to the score.
+const LIVES_CAP: u8 = 6;
 
 pub struct LivesCount {
     pub(crate) count: u8,
@@ -23,6 +25,10 @@ impl LivesCount {
     pub fn decrease(&mut self) {
         self.count -= 1;
     }
+    /// Up to `LIVES_CAP`, extra lives past it are lost.
+    pub fn increase(&mut self) {
+        self.count = self.count.saturating_add(1).min(LIVES_CAP);
+    }
 }
 
 impl Actor for LivesCount {
@@ -35,19 +41,25 @@ impl Actor for LivesCount {
     }
 
     fn draw(&self, fb: &mut impl FrameBufferInterface) {
-        const COORDINATES: [Coordinates; 3] = [
-            Coordinates::new(UI_LIFES_X, UI_LIFES_Y),
-            Coordinates::new(
-                UI_LIFES_X + (HERO_WIDTH + UI_LIFES_X_OFFSET_BETWEEN_LIFES),
-                UI_LIFES_Y,
-            ),
-            Coordinates::new(
-                UI_LIFES_X + 2 * (HERO_WIDTH + UI_LIFES_X_OFFSET_BETWEEN_LIFES),
+        for i in 0..u32::from(self.count) {
+            let coordinate = Coordinates::new(
+                UI_LIFES_X + i * (HERO_WIDTH + UI_LIFES_X_OFFSET_BETWEEN_LIFES),
                 UI_LIFES_Y,
-            ),
-        ];
-        for coordinate in COORDINATES.iter().take(self.count as usize) {
-            fb.display_image(coordinate, HERO_SPRITE_U32, HERO_WIDTH, HERO_HEIGHT);
+            );
+            fb.display_image(&coordinate, HERO_SPRITE_U32, HERO_WIDTH, HERO_HEIGHT);
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{LivesCount, LIVES_CAP};
+
+    #[test]
+    fn test_lives_are_capped() {
+        let mut lives = LivesCount::new(LIVES_CAP - 1);
+        lives.increase();
+        lives.increase();
+        assert_eq!(lives.count, LIVES_CAP);
+    }
+}
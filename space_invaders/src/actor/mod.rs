@@ -1,16 +1,20 @@
 mod barricade;
+mod effects;
 mod enemy;
 mod hero;
 mod lives;
 mod score_count;
 pub(crate) mod shoot;
+mod ufo;
 
 pub use barricade::*;
+pub use effects::*;
 pub use enemy::*;
 pub use hero::*;
 pub use lives::*;
 pub use score_count::ScoreCount;
 pub use shoot::*;
+pub use ufo::*;
 
 use crate::framebuffer::coordinates::Coordinates;
 use crate::framebuffer::fb_trait::FrameBufferInterface;
@@ -20,11 +24,85 @@ pub struct Sprite {
     sprite: &'static [u32],
 }
 impl Sprite {
-    pub fn new(sprite: &'static [u32]) -> Self {
+    #[must_use]
+    pub const fn new(sprite: &'static [u32]) -> Self {
         Self { sprite }
     }
 }
 
+/// One frame of an `AnimatedSprite`, frames don't need to have the same size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteFrame {
+    pub sprite: Sprite,
+    pub width: u32,
+    pub height: u32,
+}
+impl SpriteFrame {
+    #[must_use]
+    pub const fn new(sprite: &'static [u32], width: u32, height: u32) -> Self {
+        Self {
+            sprite: Sprite::new(sprite),
+            width,
+            height,
+        }
+    }
+}
+
+/// A sprite made of several frames, shown one after the other.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnimatedSprite {
+    frames: &'static [SpriteFrame],
+    // 0 means the frames are only changed with `next_frame`.
+    frame_duration_ms: u64,
+    current: usize,
+    elapsed_ms: u64,
+}
+impl AnimatedSprite {
+    /// # Panics
+    /// If `frames` is empty.
+    #[must_use]
+    pub const fn new(frames: &'static [SpriteFrame], frame_duration_ms: u64) -> Self {
+        assert!(!frames.is_empty(), "An animation needs at least one frame");
+        Self {
+            frames,
+            frame_duration_ms,
+            current: 0,
+            elapsed_ms: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn current_frame(&self) -> &SpriteFrame {
+        &self.frames[self.current]
+    }
+
+    pub fn next_frame(&mut self) {
+        self.current = (self.current + 1) % self.frames.len();
+    }
+
+    /// Lets `delta_ms` pass, returns true if the frame changed.
+    pub fn update(&mut self, delta_ms: u64) -> bool {
+        if self.frame_duration_ms == 0 {
+            return false;
+        }
+        let previous = self.current;
+        self.elapsed_ms += delta_ms;
+        while self.elapsed_ms >= self.frame_duration_ms {
+            self.elapsed_ms -= self.frame_duration_ms;
+            self.next_frame();
+        }
+        previous != self.current
+    }
+
+    /// Shows the current frame on the actor.
+    pub fn apply(&self, structure: &mut ActorStructure) {
+        let frame = self.current_frame();
+        structure.sprite = Some(frame.sprite);
+        structure.width = frame.width;
+        structure.height = frame.height;
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ActorStructure {
     pub sprite: Option<Sprite>,
@@ -116,3 +194,43 @@ mod test {
         assert!(shoot.is_hit(&coordinates));
     }
 }*/
+
+#[cfg(test)]
+mod animation_test {
+    use super::{ActorStructure, AnimatedSprite, Coordinates, SpriteFrame};
+
+    static FIRST: [u32; 2] = [1, 1];
+    static SECOND: [u32; 1] = [2];
+    static FRAMES: [SpriteFrame; 2] = [
+        SpriteFrame::new(&FIRST, 2, 1),
+        SpriteFrame::new(&SECOND, 1, 1),
+    ];
+
+    #[test]
+    fn test_frames_follow_the_duration() {
+        let mut animation = AnimatedSprite::new(&FRAMES, 100);
+        assert!(!animation.update(99));
+        assert_eq!(animation.current_frame(), &FRAMES[0]);
+        assert!(animation.update(1));
+        assert_eq!(animation.current_frame(), &FRAMES[1]);
+        // loops back, even when more than one frame passed.
+        assert!(animation.update(300));
+        assert_eq!(animation.current_frame(), &FRAMES[0]);
+
+        let mut structure = ActorStructure::new(Coordinates::new(0, 0));
+        animation.next_frame();
+        animation.apply(&mut structure);
+        assert_eq!(
+            (structure.sprite, structure.width),
+            (Some(FRAMES[1].sprite), 1)
+        );
+    }
+
+    #[test]
+    fn test_no_duration_only_changes_by_hand() {
+        let mut animation = AnimatedSprite::new(&FRAMES, 0);
+        assert!(!animation.update(10_000));
+        animation.next_frame();
+        assert_eq!(animation.current_frame(), &FRAMES[1]);
+    }
+}
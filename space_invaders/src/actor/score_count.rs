@@ -1,17 +1,19 @@
 use crate::actor::{Actor, ActorStructure};
-use crate::{Color, Coordinates, FrameBufferInterface};
-use core::{cmp, mem};
+use crate::{Color, Coordinates, FrameBufferInterface, TextBuffer};
+use core::cmp;
 
 use crate::framebuffer::color;
-use crate::framebuffer::fb_trait::LETTER_WIDTH;
-use crate::{SCREEN_MARGIN, SCREEN_WIDTH};
+use crate::framebuffer::text::{TextAlign, TextStyle};
+use crate::{BONUS_LIFE_SCORE, SCREEN_MARGIN, SCREEN_WIDTH};
 
-pub const UI_MAX_SCORE_LEN: usize = "High Score: 9999 - Current Score: 9999".len();
-// +1 because it doesn't take into account the last letter's space to the end of the screen
-pub const UI_SCORE_X: u32 = SCREEN_WIDTH - (UI_MAX_SCORE_LEN as u32 + 1) * LETTER_WIDTH as u32;
+// any u32 fits.
+pub const UI_MAX_SCORE_LEN: usize =
+    "Wave: 4294967295 - High Score: 4294967295 - Current Score: 4294967295".len();
+// the text is aligned to the right, x depends on its length.
 pub const UI_SCORE_Y: u32 = SCREEN_MARGIN / 2;
-pub const UI_SCORE_COORDINATES: Coordinates = Coordinates::new(UI_SCORE_X, UI_SCORE_Y);
+pub const UI_SCORE_COORDINATES: Coordinates = Coordinates::new(SCREEN_WIDTH, UI_SCORE_Y);
 pub const UI_SCORE_COLOR: Color = color::WHITE_COLOR;
+const UI_SCORE_STYLE: TextStyle = TextStyle::new(UI_SCORE_COLOR).with_align(TextAlign::Right);
 
 pub struct ScoreCount {
     current_score: u32,
@@ -19,10 +21,11 @@ pub struct ScoreCount {
     structure: ActorStructure,
     high_score_updated: u32,
     current_score_updated: u32,
+    wave: u32,
 }
 
 impl ScoreCount {
-    pub(crate) fn new(current_score: u32, high_score: u32) -> ScoreCount {
+    pub(crate) fn new(current_score: u32, high_score: u32, wave: u32) -> ScoreCount {
         ScoreCount {
             current_score,
             high_score,
@@ -35,12 +38,16 @@ impl ScoreCount {
             },
             high_score_updated: high_score,
             current_score_updated: current_score,
+            wave,
         }
     }
-    pub(crate) fn update(&mut self, enemies_dead: usize) {
-        self.current_score_updated =
-            self.current_score + u32::try_from(enemies_dead).expect("Conversion failed");
+    /// `round_points` are the points earned since the start of this wave.
+    /// Returns true when the score just reached `BONUS_LIFE_SCORE`.
+    pub(crate) fn update(&mut self, round_points: u32) -> bool {
+        let previous_score = self.current_score_updated;
+        self.current_score_updated = self.current_score.saturating_add(round_points);
         self.high_score_updated = cmp::max(self.current_score_updated, self.high_score);
+        previous_score < BONUS_LIFE_SCORE && self.current_score_updated >= BONUS_LIFE_SCORE
     }
 }
 
@@ -54,75 +61,43 @@ impl Actor for ScoreCount {
     }
 
     fn draw(&self, fb: &mut impl FrameBufferInterface) {
-        let mut message_buf = [0u8; UI_MAX_SCORE_LEN * mem::size_of::<char>()];
-        let text = format_to_buffer(
-            &mut message_buf,
+        let text = format_score(
+            self.wave,
             self.high_score_updated,
             self.current_score_updated,
-        )
-        .expect("TODO: panic message");
-
-        let mut x = self.structure.coordinates.x();
+        );
+        // keeps a space to the end of the screen.
+        let x = self.structure.coordinates.x() - UI_SCORE_STYLE.letter_width();
         let y = self.structure.coordinates.y();
-        for c in text.chars() {
-            // right distance after each character
-            x += LETTER_WIDTH as u32;
-            fb.write_char(c, Coordinates::new(x, y), UI_SCORE_COLOR);
-        }
+        fb.draw_text(&text, Coordinates::new(x, y), UI_SCORE_STYLE);
     }
 }
 
-// Function to write formatted data into a buffer
-fn format_to_buffer(
-    buffer: &mut [u8],
-    high_score: u32,
-    current_score: u32,
-) -> Result<&str, core::fmt::Error> {
-    use core::fmt::Write;
-    let mut output = BufferWrite::new(buffer);
-    write!(
-        output,
-        "High Score: {high_score} - Current Score: {current_score}"
-    )?;
-
-    // Convert the buffer slice into a &str
-    let written_length = output.written_length();
-    let formatted_str = core::str::from_utf8(&buffer[..written_length]).unwrap();
-    Ok(formatted_str)
-}
-
-// A custom implementation of core::fmt::Write for writing into a buffer
-struct BufferWrite<'a> {
-    buffer: &'a mut [u8],
-    position: usize,
+fn format_score(wave: u32, high_score: u32, current_score: u32) -> TextBuffer<UI_MAX_SCORE_LEN> {
+    TextBuffer::from_fmt(format_args!(
+        "Wave: {wave} - High Score: {high_score} - Current Score: {current_score}"
+    ))
 }
 
-impl<'a> BufferWrite<'a> {
-    fn new(buffer: &'a mut [u8]) -> Self {
-        BufferWrite {
-            buffer,
-            position: 0,
-        }
-    }
+#[cfg(test)]
+mod test {
+    use super::{format_score, ScoreCount, UI_MAX_SCORE_LEN};
+    use crate::BONUS_LIFE_SCORE;
 
-    // Get the total number of bytes written so far
-    fn written_length(&self) -> usize {
-        self.position
+    #[test]
+    fn test_bonus_life_once() {
+        let mut score = ScoreCount::new(BONUS_LIFE_SCORE - 100, 0, 2);
+        assert!(!score.update(50));
+        assert!(score.update(100));
+        assert!(!score.update(200));
+        assert_eq!(score.current_score_updated, BONUS_LIFE_SCORE + 100);
+        assert_eq!(score.high_score_updated, BONUS_LIFE_SCORE + 100);
     }
-}
-
-// Implement the Write trait for BufferWrite
-impl<'a> core::fmt::Write for BufferWrite<'a> {
-    fn write_str(&mut self, s: &str) -> core::fmt::Result {
-        let bytes = s.as_bytes();
-        let remaining_space = self.buffer.len() - self.position;
 
-        if bytes.len() <= remaining_space {
-            self.buffer[self.position..self.position + bytes.len()].copy_from_slice(bytes);
-            self.position += bytes.len();
-            Ok(())
-        } else {
-            Err(core::fmt::Error)
-        }
+    #[test]
+    fn test_large_scores_fit() {
+        let text = format_score(u32::MAX, u32::MAX, u32::MAX);
+        assert_eq!(text.len(), UI_MAX_SCORE_LEN);
+        assert!(text.ends_with("Current Score: 4294967295"));
     }
 }
@@ -1,10 +1,11 @@
 use crate::actor::{
-    Actor, ActorStructure, Barricade, Enemies, Enemy, Hero, HERO_HEIGHT, HERO_WIDTH, TOTAL_ENEMIES,
+    Actor, ActorStructure, Barricade, EffectKind, Effects, Enemies, Enemy, Hero, Ufo, HERO_HEIGHT,
+    HERO_WIDTH, TOTAL_ENEMIES,
 };
 use crate::framebuffer::color::SHOT_COLOR;
 use crate::framebuffer::fb_trait::FrameBufferInterface;
 use crate::framebuffer::{Color, Coordinates};
-use crate::SCREEN_HEIGHT;
+use crate::{Level, SCREEN_HEIGHT};
 
 pub const SHOOT_BOX_WIDTH: u32 = 3;
 pub const SHOOT_BOX_HEIGHT: u32 = 7;
@@ -21,7 +22,8 @@ const SHOOT_SPEED: f64 = 400.0 / 1000.0;
 
 pub const SHOOT_SPAWN_OFFSET_Y: u32 = HERO_HEIGHT + 10;
 
-pub const SHOOT_ENEMY_MAX: usize = 3;
+/// The most enemy shots there can be on screen, the actual limit depends on the `Level`.
+pub const SHOOT_ENEMY_MAX: usize = 8;
 pub const SHOOT_HERO_MAX: usize = 3;
 
 // max shots available to render at a time
@@ -90,7 +92,10 @@ impl Shoot {
 
     pub(crate) fn out_of_screen(&self, screen_height: u32) -> bool {
         let coordinates = &self.structure.coordinates;
-        (coordinates.y() as i32) - (self.structure.height as i32) <= 0
+        coordinates
+            .y_i32()
+            .saturating_sub_unsigned(self.structure.height)
+            <= 0
             || (coordinates.y() + self.structure.height) >= (screen_height)
     }
 
@@ -110,10 +115,11 @@ pub struct Shoots {
     hero_shoots_alive: usize,
     enemy_shoots: [Shoot; SHOOT_ENEMY_MAX],
     enemy_shoots_alive: usize,
+    enemy_shoots_max: usize,
 }
 impl Shoots {
     #[must_use]
-    pub const fn new() -> Self {
+    pub fn new(level: Level) -> Self {
         let hero_shoots: [Shoot; SHOOT_HERO_MAX] =
             [Shoot::new(Coordinates::new(0, 0), ShootOwner::Hero); SHOOT_HERO_MAX];
         let enemy_shoots: [Shoot; SHOOT_ENEMY_MAX] =
@@ -124,8 +130,15 @@ impl Shoots {
             enemy_shoots,
             hero_shoots_alive: 0,
             enemy_shoots_alive: 0,
+            enemy_shoots_max: level.enemy_shoots_max(),
         }
     }
+    /// The hero's and the enemies' shoots still on screen.
+    #[must_use]
+    pub fn flying(&self) -> usize {
+        self.hero_shoots_alive + self.enemy_shoots_alive
+    }
+
     pub fn create_shoots(&mut self, shoot: Option<Shoot>, rnd: u32, enemies: &mut Enemies) {
         self.handle_hero_shoot(shoot);
         self.handle_enemies_shoot(rnd, enemies);
@@ -143,7 +156,7 @@ impl Shoots {
     }
 
     fn handle_enemies_shoot(&mut self, rnd: u32, enemies: &mut Enemies) {
-        if self.enemy_shoots_alive >= SHOOT_ENEMY_MAX {
+        if self.enemy_shoots_alive >= self.enemy_shoots_max {
             return;
         }
         let enemy_shooting = rnd as usize % (TOTAL_ENEMIES - enemies.enemies_dead);
@@ -188,28 +201,41 @@ impl Shoots {
         &mut self,
         hero: &mut Hero,
         enemies: &mut Enemies,
+        ufo: &mut Ufo,
         barricades: &mut [Barricade],
         barricades_alive: &mut usize,
+        effects: &mut Effects,
     ) {
         // this is not the best way to do it, but it works.
         // The issue here is that if the loop runs really slowly, then the shoot will overlap
         // with the enemies in very few positions. OFC, if the game is running with so few fps,
         // it would be unplayable anyway.
         for shoot in &mut self.hero_shoots.iter_mut().filter(|sh| sh.is_alive()) {
-            if let Some((actor, is_enemy)) = enemies
+            if ufo.is_alive() && shoot.is_hit(ufo.get_structure()) {
+                effects.spawn(EffectKind::Explosion, ufo.get_structure());
+                ufo.hit();
+                shoot.structure.alive = false;
+                self.hero_shoots_alive -= 1;
+                break;
+            }
+            if let Some(enemy) = enemies
                 .enemies
                 .iter_mut()
-                .map(|e| (&mut e.structure, 1))
-                .chain(barricades.iter_mut().map(|e| (&mut e.structure, 0)))
-                .find(|a| a.0.alive && shoot.is_hit(a.0))
+                .find(|e| e.is_alive() && shoot.is_hit(e.get_structure()))
             {
-                actor.alive = false;
-                enemies.enemies_dead += is_enemy;
-                *barricades_alive -= usize::from(is_enemy == 0);
+                enemy.structure.alive = false;
+                effects.spawn(EffectKind::Explosion, enemy.get_structure());
+                enemies.enemies_dead += 1;
+                enemies.points += enemy.points;
                 shoot.structure.alive = false;
                 self.hero_shoots_alive -= 1;
                 break;
             }
+            // coming from below, it hits the bottom of the barricade first.
+            if Self::hit_barricades(shoot, true, barricades, barricades_alive, effects) {
+                self.hero_shoots_alive -= 1;
+                break;
+            }
         }
         for shoot in &mut self.enemy_shoots.iter_mut().filter(|sh| sh.is_alive()) {
             if shoot.is_hit(hero.get_structure()) {
@@ -217,17 +243,37 @@ impl Shoots {
                 hero.structure.alive = false;
                 self.enemy_shoots_alive -= 1;
             }
-            for b in barricades.iter_mut().filter(|ba| ba.is_alive()) {
-                if shoot.is_hit(b.get_structure()) {
-                    shoot.structure.alive = false;
-                    b.structure.alive = false;
-                    *barricades_alive -= 1;
-                    self.enemy_shoots_alive -= 1;
-                    break;
-                }
+            if Self::hit_barricades(shoot, false, barricades, barricades_alive, effects) {
+                self.enemy_shoots_alive -= 1;
             }
         }
     }
+
+    /// Carves a crater where `shoot` hits a barricade, if it does. The shoot is gone then.
+    fn hit_barricades(
+        shoot: &mut Shoot,
+        from_below: bool,
+        barricades: &mut [Barricade],
+        barricades_alive: &mut usize,
+        effects: &mut Effects,
+    ) -> bool {
+        let Some((barricade, point)) =
+            barricades
+                .iter_mut()
+                .filter(|b| b.is_alive())
+                .find_map(|b| {
+                    let point = b.hit_point(&shoot.structure, from_below)?;
+                    Some((b, point))
+                })
+        else {
+            return false;
+        };
+        barricade.erode(&point);
+        *barricades_alive -= usize::from(!barricade.is_alive());
+        effects.spawn(EffectKind::Debris, &ActorStructure::new(point));
+        shoot.structure.alive = false;
+        true
+    }
     pub fn draw(&self, fb: &mut impl FrameBufferInterface) {
         for shoot in self
             .enemy_shoots
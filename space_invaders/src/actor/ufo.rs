@@ -0,0 +1,159 @@
+use crate::actor::{Actor, ActorStructure, AnimatedSprite, SpriteFrame, HERO_HEIGHT};
+use crate::framebuffer::Coordinates;
+use crate::{RandomGenerator, SCREEN_MARGIN, SCREEN_WIDTH};
+
+static UFO_SPRITE: &[u32] = crate::include_bytes_align_as!(u32, "../../../assets/ufo.data");
+static UFO_SPRITE_1: &[u32] = crate::include_bytes_align_as!(u32, "../../../assets/ufo-1.data");
+
+// blinking lights.
+static UFO_FRAMES: [SpriteFrame; 2] = [
+    SpriteFrame::new(UFO_SPRITE, UFO_WIDTH, UFO_HEIGHT),
+    SpriteFrame::new(UFO_SPRITE_1, UFO_WIDTH, UFO_HEIGHT),
+];
+const UFO_FRAME_DURATION_MS: u64 = 150;
+
+const UFO_WIDTH: u32 = 48;
+pub(crate) const UFO_HEIGHT: u32 = 21;
+// right below the lives and the score, above the enemies.
+const UFO_Y: u32 = SCREEN_MARGIN + HERO_HEIGHT;
+
+const UFO_SPEED_PER_MS: f64 = 150.0 / 1000.0; // pixels per millisecond
+
+// game time between a UFO leaving the screen and the next one showing up.
+const UFO_SPAWN_INTERVAL_MS: u64 = 20_000;
+
+/// How many points shooting a UFO is worth, one is picked at random for every UFO.
+const UFO_POINTS: [u32; 4] = [50, 100, 150, 300];
+
+/// The mystery ship crossing the top of the screen every now and then.
+pub struct Ufo {
+    pub(crate) structure: ActorStructure,
+    animation: AnimatedSprite,
+    moving_right: bool,
+    since_last_ufo_ms: u64,
+    // what the current UFO is worth.
+    value: u32,
+    /// points earned by shooting UFOs.
+    pub(crate) points: u32,
+}
+
+impl Default for Ufo {
+    fn default() -> Self {
+        let animation = AnimatedSprite::new(&UFO_FRAMES, UFO_FRAME_DURATION_MS);
+        let mut structure = ActorStructure::new(Coordinates::new(SCREEN_MARGIN, UFO_Y));
+        structure.alive = false;
+        animation.apply(&mut structure);
+        Self {
+            structure,
+            animation,
+            moving_right: true,
+            since_last_ufo_ms: 0,
+            value: 0,
+            points: 0,
+        }
+    }
+}
+
+impl Ufo {
+    fn spawn(&mut self, rng: &mut impl RandomGenerator) {
+        let rnd = rng.next_u32();
+        self.moving_right = rnd & 1 == 0;
+        self.value = UFO_POINTS[(rnd >> 1) as usize % UFO_POINTS.len()];
+        let x = if self.moving_right {
+            SCREEN_MARGIN
+        } else {
+            SCREEN_WIDTH - SCREEN_MARGIN - UFO_WIDTH
+        };
+        self.structure.coordinates = Coordinates::new(x, UFO_Y);
+        self.structure.alive = true;
+    }
+
+    fn despawn(&mut self) {
+        self.structure.alive = false;
+        self.since_last_ufo_ms = 0;
+    }
+
+    pub(crate) fn handle_movement(&mut self, delta_ms: u64, rng: &mut impl RandomGenerator) {
+        if !self.is_alive() {
+            self.since_last_ufo_ms += delta_ms;
+            if self.since_last_ufo_ms >= UFO_SPAWN_INTERVAL_MS {
+                self.spawn(rng);
+            }
+            return;
+        }
+        if self.animation.update(delta_ms) {
+            self.animation.apply(&mut self.structure);
+        }
+        #[allow(clippy::cast_precision_loss)] // a frame is a few milliseconds.
+        let offset = UFO_SPEED_PER_MS * delta_ms as f64;
+        let coordinates = &mut self.structure.coordinates;
+        if self.moving_right {
+            coordinates.add_virtual_x(offset);
+            if coordinates.x() + UFO_WIDTH >= SCREEN_WIDTH - SCREEN_MARGIN {
+                self.despawn();
+            }
+        } else {
+            coordinates.sub_virtual_x(offset);
+            if coordinates.virtual_x <= f64::from(SCREEN_MARGIN) {
+                self.despawn();
+            }
+        }
+    }
+
+    /// The UFO got shot: it's gone and its points are earned.
+    pub(crate) fn hit(&mut self) {
+        self.points += self.value;
+        self.despawn();
+    }
+}
+
+impl Actor for Ufo {
+    fn get_structure(&self) -> &ActorStructure {
+        &self.structure
+    }
+
+    fn set_coordinates(&mut self, coordinates: Coordinates) {
+        self.structure.coordinates = coordinates;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Ufo, UFO_POINTS, UFO_SPAWN_INTERVAL_MS};
+    use crate::actor::Actor;
+    use crate::XorShift64Star;
+
+    #[test]
+    fn test_ufo_spawns_crosses_and_leaves() {
+        let mut rng = XorShift64Star::new(7);
+        let mut ufo = Ufo::default();
+        ufo.handle_movement(UFO_SPAWN_INTERVAL_MS - 1, &mut rng);
+        assert!(!ufo.is_alive());
+        ufo.handle_movement(1, &mut rng);
+        assert!(ufo.is_alive());
+
+        let start_x = ufo.get_coordinates().virtual_x;
+        ufo.handle_movement(100, &mut rng);
+        assert!((ufo.get_coordinates().virtual_x - start_x).abs() > 1.0);
+
+        let mut steps = 0;
+        while ufo.is_alive() {
+            ufo.handle_movement(33, &mut rng);
+            steps += 1;
+            assert!(steps < 1000, "the ufo never left the screen");
+        }
+        // it will be back after the interval.
+        ufo.handle_movement(UFO_SPAWN_INTERVAL_MS, &mut rng);
+        assert!(ufo.is_alive());
+    }
+
+    #[test]
+    fn test_hit_awards_points() {
+        let mut rng = XorShift64Star::new(3);
+        let mut ufo = Ufo::default();
+        ufo.handle_movement(UFO_SPAWN_INTERVAL_MS, &mut rng);
+        ufo.hit();
+        assert!(!ufo.is_alive());
+        assert!(UFO_POINTS.contains(&ufo.points));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::framebuffer::color::{CLEAR_COLOR, WHITE_COLOR};
+use crate::{
+    Coordinates, FrameBufferInterface, TextBuffer, TextSize, TextStyle, SCREEN_HEIGHT,
+    SCREEN_MARGIN,
+};
+use core::time::Duration;
+use log::info;
+
+/// The timings shown are averaged over this many samples.
+const AVERAGE_SAMPLES: usize = 30;
+/// The timings are logged every this many frames, 10 seconds at `FPS`, even when not shown.
+const LOG_EVERY_FRAMES: u32 = 300;
+const LINE_LEN: usize = 48;
+const LINE_SPACING: u32 = 2;
+const STYLE: TextStyle = TextStyle::new(WHITE_COLOR)
+    .with_size(TextSize::Small)
+    .with_background(CLEAR_COLOR);
+
+/// The average of the last `AVERAGE_SAMPLES` durations pushed.
+struct RollingAverage {
+    samples: [Duration; AVERAGE_SAMPLES],
+    next: usize,
+    len: usize,
+}
+
+impl RollingAverage {
+    const fn new() -> Self {
+        Self {
+            samples: [Duration::ZERO; AVERAGE_SAMPLES],
+            next: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, sample: Duration) {
+        self.samples[self.next] = sample;
+        self.next = (self.next + 1) % AVERAGE_SAMPLES;
+        self.len = (self.len + 1).min(AVERAGE_SAMPLES);
+    }
+
+    fn average(&self) -> Duration {
+        if self.len == 0 {
+            return Duration::ZERO;
+        }
+        let len = u32::try_from(self.len).expect("Conversion failed");
+        self.samples[..self.len].iter().sum::<Duration>() / len
+    }
+}
+
+/// How many actors are in play, shown under the timings.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct ActorCounts {
+    pub(crate) enemies: usize,
+    pub(crate) shoots: usize,
+    pub(crate) effects: usize,
+}
+
+/// Frame timings and actor counts, drawn in the bottom left corner when visible.
+/// See `KeyPressedKeys::Debug`.
+pub(crate) struct DebugOverlay {
+    visible: bool,
+    // between two frames drawn.
+    frame: RollingAverage,
+    // a game loop iteration, without drawing.
+    update: RollingAverage,
+    draw: RollingAverage,
+    frames_drawn: u32,
+}
+
+impl DebugOverlay {
+    pub(crate) const fn new() -> Self {
+        Self {
+            visible: false,
+            frame: RollingAverage::new(),
+            update: RollingAverage::new(),
+            draw: RollingAverage::new(),
+            frames_drawn: 0,
+        }
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub(crate) const fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub(crate) fn record_update(&mut self, update: Duration) {
+        self.update.push(update);
+    }
+
+    /// A frame was drawn, in `draw`, `since_last` after the previous one.
+    pub(crate) fn record_frame(&mut self, since_last: Duration, draw: Duration) {
+        self.frame.push(since_last);
+        self.draw.push(draw);
+        self.frames_drawn = self.frames_drawn.wrapping_add(1);
+        if self.frames_drawn.is_multiple_of(LOG_EVERY_FRAMES) {
+            info!(
+                "frame: {}us update: {}us draw: {}us",
+                self.frame.average().as_micros(),
+                self.update.average().as_micros(),
+                self.draw.average().as_micros()
+            );
+        }
+    }
+
+    pub(crate) fn draw(&self, fb: &mut impl FrameBufferInterface, counts: ActorCounts) {
+        let frame = self.frame.average();
+        let fps = if frame.is_zero() {
+            0
+        } else {
+            1_000_000 / frame.as_micros().max(1)
+        };
+        let lines: [TextBuffer<LINE_LEN>; 3] = [
+            TextBuffer::from_fmt(format_args!("fps: {fps} frame: {}us", frame.as_micros())),
+            TextBuffer::from_fmt(format_args!(
+                "update: {}us draw: {}us",
+                self.update.average().as_micros(),
+                self.draw.average().as_micros()
+            )),
+            TextBuffer::from_fmt(format_args!(
+                "enemies: {} shots: {} effects: {}",
+                counts.enemies, counts.shoots, counts.effects
+            )),
+        ];
+        let line_height = STYLE.letter_height() + LINE_SPACING;
+        let lines_len = u32::try_from(lines.len()).expect("Conversion failed");
+        let mut y = SCREEN_HEIGHT - SCREEN_MARGIN / 2 - lines_len * line_height;
+        for line in &lines {
+            fb.draw_text(line, Coordinates::new(SCREEN_MARGIN / 2, y), STYLE);
+            y += line_height;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RollingAverage, AVERAGE_SAMPLES};
+    use core::time::Duration;
+
+    #[test]
+    fn test_rolling_average() {
+        let mut average = RollingAverage::new();
+        assert_eq!(average.average(), Duration::ZERO);
+        average.push(Duration::from_millis(10));
+        average.push(Duration::from_millis(20));
+        assert_eq!(average.average(), Duration::from_millis(15));
+        // the first two are pushed out.
+        for _ in 0..AVERAGE_SAMPLES {
+            average.push(Duration::from_millis(30));
+        }
+        assert_eq!(average.average(), Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+use core::ops::Range;
+
+/// The part of the playfield that drawing is limited to: anything outside is left untouched.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ClipRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ClipRect {
+    /// The whole playfield.
+    pub const FULL: Self = Self::new(0, 0, SCREEN_WIDTH, SCREEN_HEIGHT);
+
+    #[must_use]
+    pub const fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// The columns and rows of the rectangle that are inside, within the playfield as well.
+    /// None if nothing is.
+    pub(crate) fn clip(
+        &self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Option<(Range<u32>, Range<u32>)> {
+        let columns = clip_range(x, width, self.x, self.right(), SCREEN_WIDTH)?;
+        let rows = clip_range(y, height, self.y, self.bottom(), SCREEN_HEIGHT)?;
+        Some((columns, rows))
+    }
+
+    /// The part of the rectangle inside the playfield, None if there's nothing.
+    pub(crate) fn visible(x: i32, y: i32, width: u32, height: u32) -> Option<Self> {
+        let (columns, rows) = Self::FULL.clip(x, y, width, height)?;
+        Some(Self::new(
+            columns.start,
+            rows.start,
+            columns.len().try_into().expect("Conversion failed"),
+            rows.len().try_into().expect("Conversion failed"),
+        ))
+    }
+
+    /// The part of both rectangles, None if they don't overlap.
+    pub(crate) fn intersection(&self, other: &Self) -> Option<Self> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        (x < right && y < bottom).then(|| Self::new(x, y, right - x, bottom - y))
+    }
+
+    /// Overlapping or right next to each other.
+    pub(crate) fn touches(&self, other: &Self) -> bool {
+        self.x <= other.right()
+            && other.x <= self.right()
+            && self.y <= other.bottom()
+            && other.y <= self.bottom()
+    }
+
+    /// The smallest rectangle covering both.
+    pub(crate) fn union(&self, other: &Self) -> Self {
+        let (x, y) = (self.x.min(other.x), self.y.min(other.y));
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+        Self::new(x, y, right - x, bottom - y)
+    }
+
+    fn right(&self) -> u32 {
+        self.x.saturating_add(self.width)
+    }
+
+    fn bottom(&self) -> u32 {
+        self.y.saturating_add(self.height)
+    }
+}
+
+fn clip_range(start: i32, len: u32, min: u32, max: u32, screen_len: u32) -> Option<Range<u32>> {
+    let end = (i64::from(start) + i64::from(len)).min(i64::from(max.min(screen_len)));
+    let start = i64::from(start).max(i64::from(min));
+    (start < end).then(|| {
+        u32::try_from(start).expect("Conversion failed")
+            ..u32::try_from(end).expect("Conversion failed")
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClipRect;
+    use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+    #[test]
+    fn test_clip() {
+        let (width, height) = (
+            i32::try_from(SCREEN_WIDTH).unwrap(),
+            i32::try_from(SCREEN_HEIGHT).unwrap(),
+        );
+        let clip = ClipRect::FULL;
+        assert_eq!(clip.clip(10, 20, 5, 5), Some((10..15, 20..25)));
+        // partly off screen, on every side.
+        assert_eq!(clip.clip(-3, -4, 5, 5), Some((0..2, 0..1)));
+        assert_eq!(
+            clip.clip(width - 2, height - 1, 5, 5),
+            Some((
+                SCREEN_WIDTH - 2..SCREEN_WIDTH,
+                SCREEN_HEIGHT - 1..SCREEN_HEIGHT
+            ))
+        );
+        assert_eq!(clip.clip(-5, 0, 5, 5), None);
+        assert_eq!(clip.clip(0, height, 5, 5), None);
+
+        let clip = ClipRect::new(100, 100, 50, u32::MAX);
+        assert_eq!(
+            clip.clip(90, 700, 20, 40),
+            Some((100..110, 700..SCREEN_HEIGHT))
+        );
+        assert_eq!(clip.clip(150, 100, 20, 40), None);
+    }
+
+    #[test]
+    fn test_intersection() {
+        let rect = ClipRect::new(10, 10, 20, 20);
+        assert_eq!(
+            rect.intersection(&ClipRect::new(25, 0, 100, 15)),
+            Some(ClipRect::new(25, 10, 5, 5))
+        );
+        assert_eq!(rect.intersection(&ClipRect::FULL), Some(rect));
+        // right next to it.
+        assert_eq!(rect.intersection(&ClipRect::new(30, 10, 5, 5)), None);
+    }
+}
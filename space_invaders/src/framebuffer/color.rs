@@ -12,7 +12,7 @@ impl Color {
     }
 
     const fn rgb_u32(red: u8, green: u8, blue: u8) -> u32 {
-        (255 << 28 | (red as u32) << 16) | ((green as u32) << 8) | (blue as u32)
+        (OPAQUE << ALPHA_SHIFT | (red as u32) << 16) | ((green as u32) << 8) | (blue as u32)
     }
     // inlined to increase performance by 5~ ms per loop
     #[inline(always)]
@@ -21,8 +21,39 @@ impl Color {
     }
 }
 
+/// The high byte of a pixel is its alpha: 0 is fully transparent, 255 fully opaque.
+const ALPHA_SHIFT: u32 = 24;
+const OPAQUE: u32 = 0xff;
+
+/// Sprite pixels of this colour aren't drawn: it's the background of every asset.
+pub const TRANSPARENT_KEY: u32 = 0xff00_0000;
+
+#[inline]
+pub(crate) fn is_opaque(pixel: u32) -> bool {
+    pixel >> ALPHA_SHIFT == OPAQUE && pixel != TRANSPARENT_KEY
+}
+
+#[inline]
+pub(crate) fn is_transparent(pixel: u32) -> bool {
+    pixel >> ALPHA_SHIFT == 0 || pixel == TRANSPARENT_KEY
+}
+
+/// `over` drawn on top of `under`, by its alpha. The result is opaque.
+pub(crate) fn blend(over: u32, under: u32) -> u32 {
+    let alpha = over >> ALPHA_SHIFT;
+    let mut blended = OPAQUE << ALPHA_SHIFT;
+    for shift in [0, 8, 16] {
+        let over = (over >> shift) & 0xff;
+        let under = (under >> shift) & 0xff;
+        blended |= ((over * alpha + under * (OPAQUE - alpha)) / OPAQUE) << shift;
+    }
+    blended
+}
+
+/// What `FrameBufferInterface::clear_screen` fills the screen with.
+pub(crate) const CLEAR_COLOR: Color = Color { rgb: 0 };
+
 // pub const BLACK_COLOR: Color = Color::new(0, 0, 0);
 
 pub const WHITE_COLOR: Color = Color::new(255, 255, 255);
-pub const BLUE_COLOR: Color = Color::new(0, 0, 255);
 pub const SHOT_COLOR: Color = Color::new(252, 186, 3);
@@ -31,6 +31,22 @@ impl Coordinates {
         self.virtual_y as usize
     }
 
+    /// Unlike `x`, negative when left of the playfield.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // saturates, it's off screen anyway.
+    pub fn x_i32(&self) -> i32 {
+        self.virtual_x as i32
+    }
+
+    /// Unlike `y`, negative when above the playfield.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // saturates, it's off screen anyway.
+    pub fn y_i32(&self) -> i32 {
+        self.virtual_y as i32
+    }
+
     pub fn add_virtual_x(&mut self, x: f64) {
         self.virtual_x += x;
     }
@@ -0,0 +1,405 @@
+//! Dirty rectangles: instead of clearing and drawing the whole screen every frame, only the
+//! parts where something is drawn now, or was drawn in the frame the buffer still holds, are.
+//! Drawing a frame then takes three steps: `BoundsRecorder` finds what it draws, `DirtyTracker`
+//! what to clear, and `DirtyFrameBuffer` draws it there.
+
+use crate::framebuffer::clip::ClipRect;
+use crate::framebuffer::color::Color;
+use crate::framebuffer::coordinates::Coordinates;
+use crate::framebuffer::fb_trait::FrameBufferInterface;
+use crate::framebuffer::text::TextStyle;
+use crate::framebuffer::viewport::Viewport;
+
+/// Rects touching each other are merged as they're added, so they never overlap. This is how
+/// many separate ones are kept: past it, they're merged anyway.
+const MAX_RECTS: usize = 128;
+
+/// The most buffers the drawing can alternate between, see `FrameBufferInterface::buffer_count`.
+const MAX_BUFFERS: usize = 2;
+
+/// The areas of the playfield that were drawn.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Rects {
+    rects: [ClipRect; MAX_RECTS],
+    len: usize,
+}
+
+impl Rects {
+    pub(crate) const fn new() -> Self {
+        Self {
+            rects: [ClipRect::new(0, 0, 0, 0); MAX_RECTS],
+            len: 0,
+        }
+    }
+
+    pub(crate) fn add(&mut self, rect: ClipRect) {
+        let mut rect = rect;
+        loop {
+            // once merged, it can touch the ones already checked.
+            let mut i = 0;
+            while i < self.len {
+                if self.rects[i].touches(&rect) {
+                    rect = rect.union(&self.rects[i]);
+                    self.len -= 1;
+                    self.rects[i] = self.rects[self.len];
+                    i = 0;
+                } else {
+                    i += 1;
+                }
+            }
+            if self.len < MAX_RECTS {
+                self.rects[self.len] = rect;
+                self.len += 1;
+                return;
+            }
+            // full: merged with the last one, which can then touch others.
+            self.len -= 1;
+            rect = rect.union(&self.rects[self.len]);
+        }
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &ClipRect> {
+        self.rects[..self.len].iter()
+    }
+}
+
+/// Remembers what was drawn in the last frames, so the next frame only clears what the buffer
+/// it's drawn into still shows.
+pub(crate) struct DirtyTracker {
+    // what was drawn in each of the last frames, the latest last.
+    history: [Rects; MAX_BUFFERS],
+    // frames to draw in full before the history matches every buffer.
+    full_redraws: usize,
+}
+
+impl DirtyTracker {
+    pub(crate) const fn new() -> Self {
+        Self {
+            history: [Rects::new(); MAX_BUFFERS],
+            full_redraws: MAX_BUFFERS,
+        }
+    }
+
+    /// The buffers show something else than the last frames, e.g. an overlay: they're drawn in
+    /// full again.
+    pub(crate) fn invalidate(&mut self) {
+        self.full_redraws = MAX_BUFFERS;
+    }
+
+    /// `drawn` is what the next frame draws, into a buffer that shows the frame from
+    /// `buffer_count` frames ago. Returns the rects to clear and draw again, None to clear the
+    /// screen and draw everything.
+    pub(crate) fn next_frame(&mut self, drawn: &Rects, buffer_count: usize) -> Option<Rects> {
+        let stale = self.history[MAX_BUFFERS - buffer_count.clamp(1, MAX_BUFFERS)];
+        self.history.rotate_left(1);
+        self.history[MAX_BUFFERS - 1] = *drawn;
+        if self.full_redraws > 0 || buffer_count > MAX_BUFFERS {
+            self.full_redraws = self.full_redraws.saturating_sub(1);
+            return None;
+        }
+        let mut dirty = *drawn;
+        for rect in stale.iter() {
+            dirty.add(*rect);
+        }
+        Some(dirty)
+    }
+}
+
+/// Draws nothing, only records where the drawing happens.
+pub(crate) struct BoundsRecorder {
+    pub(crate) rects: Rects,
+    clip: ClipRect,
+}
+
+impl BoundsRecorder {
+    pub(crate) const fn new() -> Self {
+        Self {
+            rects: Rects::new(),
+            clip: ClipRect::FULL,
+        }
+    }
+
+    fn record(&mut self, point: &Coordinates, width: u32, height: u32) {
+        if let Some(rect) = ClipRect::visible(point.x_i32(), point.y_i32(), width, height) {
+            self.rects.add(rect);
+        }
+    }
+}
+
+impl FrameBufferInterface for BoundsRecorder {
+    fn draw_rect_fill(&mut self, point: &Coordinates, width: u32, height: u32, _color: Color) {
+        self.record(point, width, height);
+    }
+
+    fn draw_text(&mut self, text: &str, pos: Coordinates, style: TextStyle) {
+        let left = Coordinates {
+            virtual_x: f64::from(style.left(text, pos.x_i32())),
+            ..pos
+        };
+        self.record(&left, style.width(text), style.letter_height());
+    }
+
+    fn draw_rect(&mut self, point: Coordinates, width: u32, height: u32, _color: Color) {
+        // the right and bottom sides are drawn past the width and height.
+        self.record(&point, width + 1, height + 1);
+    }
+
+    fn raw_buffer(&mut self) -> &mut [u32] {
+        &mut []
+    }
+
+    fn use_pixel(&mut self, _x_usize: usize, _y_usize: usize, _color: Color) {}
+
+    fn clip(&self) -> ClipRect {
+        self.clip
+    }
+
+    fn set_clip(&mut self, clip: ClipRect) {
+        self.clip = clip;
+    }
+
+    fn read_pixel(&mut self, _x_usize: usize, _y_usize: usize) -> u32 {
+        0
+    }
+
+    fn display_image_scaled(
+        &mut self,
+        top_left: &Coordinates,
+        _image: &[u32],
+        width: u32,
+        height: u32,
+        scale: u32,
+        _flip_horizontally: bool,
+    ) {
+        self.record(top_left, width * scale, height * scale);
+    }
+
+    fn clear_screen(&mut self) {}
+
+    fn update(&mut self) {}
+}
+
+/// Draws through `inner`, only inside `rects`: every primitive is drawn, clipped, in each of the
+/// rects it overlaps. As they don't overlap each other, no pixel is drawn twice. The clip set by
+/// the caller still applies, within each rect.
+pub(crate) struct DirtyFrameBuffer<'a, F> {
+    inner: &'a mut F,
+    rects: &'a Rects,
+}
+
+impl<'a, F: FrameBufferInterface> DirtyFrameBuffer<'a, F> {
+    pub(crate) fn new(inner: &'a mut F, rects: &'a Rects) -> Self {
+        Self { inner, rects }
+    }
+
+    fn in_each_rect(
+        &mut self,
+        point: &Coordinates,
+        width: u32,
+        height: u32,
+        draw: impl Fn(&mut F),
+    ) {
+        let (x, y) = (point.x_i32(), point.y_i32());
+        let clip = self.inner.clip();
+        for rect in self.rects.iter() {
+            let Some(rect) = rect.intersection(&clip) else {
+                continue;
+            };
+            if rect.clip(x, y, width, height).is_some() {
+                self.inner.set_clip(rect);
+                draw(self.inner);
+            }
+        }
+        self.inner.set_clip(clip);
+    }
+}
+
+impl<F: FrameBufferInterface> FrameBufferInterface for DirtyFrameBuffer<'_, F> {
+    fn draw_rect_fill(&mut self, point: &Coordinates, width: u32, height: u32, color: Color) {
+        self.in_each_rect(point, width, height, |fb| {
+            fb.draw_rect_fill(point, width, height, color);
+        });
+    }
+
+    fn draw_text(&mut self, text: &str, pos: Coordinates, style: TextStyle) {
+        let left = Coordinates {
+            virtual_x: f64::from(style.left(text, pos.x_i32())),
+            ..pos
+        };
+        self.in_each_rect(&left, style.width(text), style.letter_height(), |fb| {
+            fb.draw_text(text, pos, style);
+        });
+    }
+
+    fn draw_rect(&mut self, point: Coordinates, width: u32, height: u32, color: Color) {
+        self.in_each_rect(&point, width + 1, height + 1, |fb| {
+            fb.draw_rect(point, width, height, color);
+        });
+    }
+
+    fn raw_buffer(&mut self) -> &mut [u32] {
+        self.inner.raw_buffer()
+    }
+
+    fn width(&self) -> usize {
+        self.inner.width()
+    }
+
+    fn stride(&self) -> usize {
+        self.inner.stride()
+    }
+
+    fn width_u32(&self) -> u32 {
+        self.inner.width_u32()
+    }
+
+    fn height_u32(&self) -> u32 {
+        self.inner.height_u32()
+    }
+
+    fn height(&self) -> usize {
+        self.inner.height()
+    }
+
+    fn use_pixel(&mut self, x_usize: usize, y_usize: usize, color: Color) {
+        self.inner.use_pixel(x_usize, y_usize, color);
+    }
+
+    fn viewport(&self) -> Viewport {
+        self.inner.viewport()
+    }
+
+    fn clip(&self) -> ClipRect {
+        self.inner.clip()
+    }
+
+    fn set_clip(&mut self, clip: ClipRect) {
+        self.inner.set_clip(clip);
+    }
+
+    fn read_pixel(&mut self, x_usize: usize, y_usize: usize) -> u32 {
+        self.inner.read_pixel(x_usize, y_usize)
+    }
+
+    fn display_image(&mut self, top_left: &Coordinates, image: &[u32], width: u32, height: u32) {
+        self.in_each_rect(top_left, width, height, |fb| {
+            fb.display_image(top_left, image, width, height);
+        });
+    }
+
+    fn display_image_scaled(
+        &mut self,
+        top_left: &Coordinates,
+        image: &[u32],
+        width: u32,
+        height: u32,
+        scale: u32,
+        flip_horizontally: bool,
+    ) {
+        self.in_each_rect(top_left, width * scale, height * scale, |fb| {
+            fb.display_image_scaled(top_left, image, width, height, scale, flip_horizontally);
+        });
+    }
+
+    fn clear_screen(&mut self) {
+        self.inner.clear_screen();
+    }
+
+    fn buffer_count(&self) -> usize {
+        self.inner.buffer_count()
+    }
+
+    fn update(&mut self) {
+        self.inner.update();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DirtyTracker, Rects, MAX_RECTS};
+    use crate::ClipRect;
+
+    fn rects(rects: &[ClipRect]) -> Rects {
+        let mut all = Rects::new();
+        for rect in rects {
+            all.add(*rect);
+        }
+        all
+    }
+
+    #[test]
+    fn test_touching_rects_are_merged() {
+        let all = rects(&[
+            ClipRect::new(0, 0, 10, 10),
+            ClipRect::new(20, 0, 10, 10),
+            // touches both.
+            ClipRect::new(10, 5, 10, 10),
+            ClipRect::new(100, 100, 1, 1),
+        ]);
+        assert!(all
+            .iter()
+            .copied()
+            .eq([ClipRect::new(0, 0, 30, 15), ClipRect::new(100, 100, 1, 1)]));
+    }
+
+    #[test]
+    fn test_too_many_rects_are_merged() {
+        let max = u32::try_from(MAX_RECTS).unwrap();
+        let mut all = Rects::new();
+        for i in 0..=max {
+            all.add(ClipRect::new(i * 3, 0, 1, 1));
+        }
+        assert_eq!(all.iter().count(), MAX_RECTS);
+        assert!(all
+            .iter()
+            .any(|r| *r == ClipRect::new((max - 1) * 3, 0, 4, 1)));
+    }
+
+    #[test]
+    fn test_double_buffering_clears_two_frames_ago() {
+        let frames = [
+            rects(&[ClipRect::new(0, 0, 1, 1)]),
+            rects(&[ClipRect::new(10, 0, 1, 1)]),
+            rects(&[ClipRect::new(20, 0, 1, 1)]),
+        ];
+        let mut tracker = DirtyTracker::new();
+        assert!(tracker.next_frame(&frames[0], 2).is_none());
+        assert!(tracker.next_frame(&frames[1], 2).is_none());
+        let dirty = tracker.next_frame(&frames[2], 2).unwrap();
+        assert!(dirty
+            .iter()
+            .copied()
+            .eq([ClipRect::new(20, 0, 1, 1), ClipRect::new(0, 0, 1, 1)]));
+
+        tracker.invalidate();
+        assert!(tracker.next_frame(&frames[0], 1).is_none());
+        assert!(tracker.next_frame(&frames[1], 1).is_none());
+        let dirty = tracker.next_frame(&frames[2], 1).unwrap();
+        assert!(dirty
+            .iter()
+            .copied()
+            .eq([ClipRect::new(20, 0, 1, 1), ClipRect::new(10, 0, 1, 1)]));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_caller_clip_is_kept() {
+        use super::DirtyFrameBuffer;
+        use crate::framebuffer::color::WHITE_COLOR;
+        use crate::{Coordinates, FrameBufferInterface, HeadlessFrameBuffer, SCREEN_WIDTH};
+
+        let mut fb = HeadlessFrameBuffer::new();
+        let dirty_rects = rects(&[ClipRect::new(0, 0, 100, 100)]);
+        let mut dirty = DirtyFrameBuffer::new(&mut fb, &dirty_rects);
+        let clip = ClipRect::new(50, 0, 100, 10);
+        dirty.set_clip(clip);
+        dirty.draw_rect_fill(&Coordinates::new(0, 0), 200, 200, WHITE_COLOR);
+        assert_eq!(dirty.clip(), clip);
+        let pixel = |x: u32, y: u32| fb.buffer()[(y * SCREEN_WIDTH + x) as usize];
+        assert_eq!(pixel(49, 0), 0);
+        assert_eq!(pixel(50, 0), WHITE_COLOR.rgb());
+        assert_eq!(pixel(99, 9), WHITE_COLOR.rgb());
+        assert_eq!(pixel(100, 0), 0);
+        assert_eq!(pixel(50, 10), 0);
+    }
+}
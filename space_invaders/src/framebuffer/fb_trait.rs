@@ -1,55 +1,70 @@
+use crate::framebuffer::clip::ClipRect;
 use crate::framebuffer::color;
 use crate::framebuffer::color::Color;
 use crate::framebuffer::coordinates::Coordinates;
+use crate::framebuffer::text::TextStyle;
+use crate::framebuffer::viewport::Viewport;
 use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
-use noto_sans_mono_bitmap::{get_raster, get_raster_width, FontWeight, RasterHeight};
-
-const LETTER_FONT_WEIGHT: FontWeight = FontWeight::Regular;
-const LETTER_FONT_HEIGHT: RasterHeight = RasterHeight::Size20;
-pub const LETTER_WIDTH: usize = get_raster_width(LETTER_FONT_WEIGHT, LETTER_FONT_HEIGHT);
+use core::ops::Range;
 
+/// Drawing happens in playfield coordinates, `SCREEN_WIDTH` x `SCREEN_HEIGHT`, which `viewport`
+/// maps to the actual screen. Only `use_pixel` and `raw_buffer` work on screen pixels.
+/// Everything is clipped to `clip`, so it's fine to draw partly, or fully, off screen.
 pub trait FrameBufferInterface {
     fn draw_rect_fill(&mut self, point: &Coordinates, width: u32, height: u32, color: Color) {
-        let width = width as usize;
-        let height = height as usize;
-        for y in 0..height {
-            for x in 0..width {
-                self.use_pixel(point.x_usize() + x, point.y_usize() + y, color);
-            }
-        }
+        let target = Target::of(self);
+        fill_scaled(
+            self,
+            target,
+            point.x_i32(),
+            point.y_i32(),
+            width,
+            height,
+            color,
+        );
     }
 
-    fn write_char(&mut self, c: char, coordinates: Coordinates, color: Color) {
-        let char_raster =
-            get_raster(c, LETTER_FONT_WEIGHT, LETTER_FONT_HEIGHT).expect("unsupported char");
-        for (row_i, row) in char_raster.raster().iter().enumerate() {
-            for (col_i, pixel) in row.iter().enumerate() {
-                let actual_color = if pixel.count_zeros() == 8 {
-                    color::BLUE_COLOR
-                } else {
-                    color
-                };
-                self.use_pixel(
-                    coordinates.x_usize() + col_i,
-                    coordinates.y_usize() + row_i,
-                    actual_color,
-                );
+    /// Draws `text` on one line, `pos.y()` being its top and `style.align` telling where it is
+    /// from `pos.x()`. Only the letters are drawn, unless `style.background` is set.
+    fn draw_text(&mut self, text: &str, pos: Coordinates, style: TextStyle) {
+        let target = Target::of(self);
+        let (x, y) = (style.left(text, pos.x_i32()), pos.y_i32());
+        let (letter_width, height) = (style.letter_width(), style.letter_height());
+        if !target.is_visible(x, y, style.width(text), height) {
+            return;
+        }
+        if let Some(background) = style.background {
+            fill_scaled(self, target, x, y, style.width(text), height, background);
+        }
+        for (x, c) in (x..).step_by(letter_width as usize).zip(text.chars()) {
+            if !target.is_visible(x, y, letter_width, height) {
+                continue;
+            }
+            let Some(raster) = style.raster(c) else {
+                continue;
+            };
+            for (y, row) in (y..).zip(raster.raster()) {
+                for (x, intensity) in (x..).zip(row.iter()) {
+                    if *intensity != 0 {
+                        fill_scaled(self, target, x, y, 1, 1, style.color);
+                    }
+                }
             }
         }
     }
 
     /// [x,y] the top left center
     fn draw_rect(&mut self, point: Coordinates, width: u32, height: u32, color: Color) {
-        let width = width as usize;
-        let height = height as usize;
-        for y in 0..height {
-            self.use_pixel(point.x_usize(), point.y_usize() + y, color);
-            self.use_pixel(point.x_usize() + width, point.y_usize() + y, color);
-        }
-        for x in 0..width {
-            self.use_pixel(point.x_usize() + x, point.y_usize(), color);
-            self.use_pixel(point.x_usize() + x, point.y_usize() + height, color);
-        }
+        let target = Target::of(self);
+        let (x, y) = (point.x_i32(), point.y_i32());
+        let (right, bottom) = (
+            x.saturating_add_unsigned(width),
+            y.saturating_add_unsigned(height),
+        );
+        fill_scaled(self, target, x, y, 1, height, color);
+        fill_scaled(self, target, right, y, 1, height, color);
+        fill_scaled(self, target, x, y, width, 1, color);
+        fill_scaled(self, target, x, bottom, width, 1, color);
     }
 
     fn raw_buffer(&mut self) -> &mut [u32];
@@ -65,21 +80,80 @@ pub trait FrameBufferInterface {
     fn height(&self) -> usize {
         self.height_u32() as usize
     }
+    /// Pixels from one row of `raw_buffer` to the next, rows can be padded.
+    fn stride(&self) -> usize {
+        self.width()
+    }
 
+    /// Pixels outside of the screen are ignored.
     fn use_pixel(&mut self, x_usize: usize, y_usize: usize, color: Color) {
-        let width = self.width();
-        self.raw_buffer()[width * y_usize + x_usize] = color.rgb();
+        if x_usize < self.width() && y_usize < self.height() {
+            let stride = self.stride();
+            self.raw_buffer()[stride * y_usize + x_usize] = color.rgb();
+        }
+    }
+
+    /// Where the playfield ends up on the screen.
+    fn viewport(&self) -> Viewport {
+        Viewport::fit(self.width_u32(), self.height_u32())
+    }
+
+    /// Drawing is limited to it, `ClipRect::FULL` unless set.
+    fn clip(&self) -> ClipRect;
+    fn set_clip(&mut self, clip: ClipRect);
+
+    /// The pixel at screen `x_usize`, `y_usize`, as last drawn. 0 outside of the screen.
+    fn read_pixel(&mut self, x_usize: usize, y_usize: usize) -> u32 {
+        if x_usize >= self.width() || y_usize >= self.height() {
+            return 0;
+        }
+        let stride = self.stride();
+        self.raw_buffer()[stride * y_usize + x_usize]
     }
 
     fn display_image(&mut self, top_left: &Coordinates, image: &[u32], width: u32, height: u32) {
-        let fb_width = self.width();
-        let width = width as usize;
-        for y in 0..height as usize {
-            for x in 0..width {
-                let pos: usize = y * width + x;
-                let (x, y) = (x + top_left.x_usize(), y + top_left.y_usize());
-                let index = fb_width * y + x;
-                self.raw_buffer()[index] = image[pos];
+        self.display_image_scaled(top_left, image, width, height, 1, false);
+    }
+
+    /// Draws `image`, `width` x `height` pixels, over what's already drawn: pixels are blended by
+    /// their alpha, `color::TRANSPARENT_KEY` ones are skipped. Unscaled on an unscaled screen,
+    /// runs of opaque pixels are copied to the buffer at once.
+    /// The image is drawn `scale` times bigger: every pixel becomes a `scale` x `scale` square,
+    /// `scale` must be at least 1. `flip_horizontally` mirrors it, e.g. for sprites facing left.
+    fn display_image_scaled(
+        &mut self,
+        top_left: &Coordinates,
+        image: &[u32],
+        width: u32,
+        height: u32,
+        scale: u32,
+        flip_horizontally: bool,
+    ) {
+        let target = Target::of(self);
+        let (x, y) = (top_left.x_i32(), top_left.y_i32());
+        if !target.is_visible(x, y, width * scale, height * scale) {
+            return;
+        }
+        let rows = image.chunks_exact(width as usize).take(height as usize);
+        if scale == 1 && !flip_horizontally && target.viewport.is_identity() {
+            for (y, row) in (y..).zip(rows) {
+                copy_row(self, target, x, y, row);
+            }
+            return;
+        }
+        for (y, row) in (y..).step_by(scale as usize).zip(rows) {
+            let xs = (x..).step_by(scale as usize);
+            let mut draw = |(x, pixel): (i32, &u32)| {
+                if color::is_opaque(*pixel) {
+                    fill_scaled(self, target, x, y, scale, scale, Color { rgb: *pixel });
+                } else if !color::is_transparent(*pixel) {
+                    blend_scaled(self, target, x, y, scale, *pixel);
+                }
+            };
+            if flip_horizontally {
+                xs.zip(row.iter().rev()).for_each(&mut draw);
+            } else {
+                xs.zip(row).for_each(&mut draw);
             }
         }
     }
@@ -89,6 +163,328 @@ pub trait FrameBufferInterface {
         }
     }
 
+    /// How many buffers drawing alternates between: after `update`, the next frame is drawn
+    /// over the one shown `buffer_count` frames ago.
+    fn buffer_count(&self) -> usize {
+        1
+    }
+
     // draw the local buffer of the framebuffer to the screen
     fn update(&mut self);
 }
+
+/// Where and how much of the playfield is drawn, queried once per primitive.
+#[derive(Copy, Clone)]
+struct Target {
+    viewport: Viewport,
+    clip: ClipRect,
+}
+
+impl Target {
+    fn of<F: FrameBufferInterface + ?Sized>(fb: &F) -> Self {
+        Self {
+            viewport: fb.viewport(),
+            clip: fb.clip(),
+        }
+    }
+
+    fn is_visible(self, x: i32, y: i32, width: u32, height: u32) -> bool {
+        self.clip.clip(x, y, width, height).is_some()
+    }
+
+    /// The screen pixels covered by the visible part of the playfield rectangle: a playfield
+    /// pixel covers more than one when scaled up, none at all when scaled down.
+    fn screen_rect(
+        self,
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    ) -> Option<(Range<usize>, Range<usize>)> {
+        let (columns, rows) = self.clip.clip(x, y, width, height)?;
+        let viewport = self.viewport;
+        if viewport.is_identity() {
+            return Some((
+                columns.start as usize..columns.end as usize,
+                rows.start as usize..rows.end as usize,
+            ));
+        }
+        Some((
+            viewport.x(columns.start)..viewport.x(columns.end),
+            viewport.y(rows.start)..viewport.y(rows.end),
+        ))
+    }
+}
+
+fn fill_scaled<F: FrameBufferInterface + ?Sized>(
+    fb: &mut F,
+    target: Target,
+    x: i32,
+    y: i32,
+    width: u32,
+    height: u32,
+    color: Color,
+) {
+    let Some((columns, rows)) = target.screen_rect(x, y, width, height) else {
+        return;
+    };
+    // a whole row at a time, straight into the buffer.
+    let columns = columns.start..columns.end.min(fb.width());
+    let rows = rows.start..rows.end.min(fb.height());
+    if columns.is_empty() {
+        return;
+    }
+    let stride = fb.stride();
+    let buffer = fb.raw_buffer();
+    for y in rows {
+        buffer[stride * y + columns.start..stride * y + columns.end].fill(color.rgb());
+    }
+}
+
+/// Draws one row of an image at `x`, `y`, with an identity viewport: the runs of opaque pixels
+/// are copied at once, the others are blended one by one.
+fn copy_row<F: FrameBufferInterface + ?Sized>(
+    fb: &mut F,
+    target: Target,
+    x: i32,
+    y: i32,
+    row: &[u32],
+) {
+    let mut start = 0;
+    while start < row.len() {
+        let opaque = row[start..]
+            .iter()
+            .take_while(|pixel| color::is_opaque(**pixel))
+            .count();
+        let left = x.saturating_add_unsigned(u32::try_from(start).unwrap_or(u32::MAX));
+        if opaque == 0 {
+            if !color::is_transparent(row[start]) {
+                blend_scaled(fb, target, left, y, 1, row[start]);
+            }
+            start += 1;
+            continue;
+        }
+        let run = &row[start..start + opaque];
+        start += opaque;
+        let run_width = u32::try_from(opaque).unwrap_or(u32::MAX);
+        let Some((columns, rows)) = target.clip.clip(left, y, run_width, 1) else {
+            continue;
+        };
+        // the part of the run clipped on the left.
+        let skipped = u32::try_from(i64::from(columns.start) - i64::from(left)).unwrap_or(0);
+        let (screen_y, columns) = (
+            rows.start as usize,
+            columns.start as usize..(columns.end as usize).min(fb.width()),
+        );
+        if screen_y >= fb.height() || columns.is_empty() {
+            continue;
+        }
+        let skipped = skipped as usize;
+        let stride = fb.stride();
+        fb.raw_buffer()[stride * screen_y + columns.start..stride * screen_y + columns.end]
+            .copy_from_slice(&run[skipped..skipped + columns.len()]);
+    }
+}
+
+/// Like `fill_scaled`, blending `pixel` over every screen pixel covered.
+fn blend_scaled<F: FrameBufferInterface + ?Sized>(
+    fb: &mut F,
+    target: Target,
+    x: i32,
+    y: i32,
+    size: u32,
+    pixel: u32,
+) {
+    let Some((columns, rows)) = target.screen_rect(x, y, size, size) else {
+        return;
+    };
+    for y in rows {
+        for x in columns.clone() {
+            let under = fb.read_pixel(x, y);
+            fb.use_pixel(
+                x,
+                y,
+                Color {
+                    rgb: color::blend(pixel, under),
+                },
+            );
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::framebuffer::color::{TRANSPARENT_KEY, WHITE_COLOR};
+    use crate::{
+        ClipRect, Color, Coordinates, FrameBufferInterface, HeadlessFrameBuffer, TextAlign,
+        TextStyle, SCREEN_HEIGHT, SCREEN_WIDTH,
+    };
+
+    const IMAGE: [u32; 4] = [0xff00_0001, 0xff00_0002, 0xff00_0003, 0xff00_0004];
+
+    fn pixel(fb: &HeadlessFrameBuffer, x: u32, y: u32) -> u32 {
+        fb.buffer()[(y * SCREEN_WIDTH + x) as usize]
+    }
+
+    #[test]
+    fn test_display_image_scaled() {
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.display_image_scaled(&Coordinates::new(10, 20), &IMAGE, 2, 2, 3, false);
+        for (x, y, i) in [
+            (10, 20, 0),
+            (12, 22, 0),
+            (13, 20, 1),
+            (15, 22, 1),
+            (10, 23, 2),
+        ] {
+            assert_eq!(pixel(&fb, x, y), IMAGE[i], "at ({x}, {y})");
+        }
+        assert_eq!(pixel(&fb, 15, 25), IMAGE[3]);
+        assert_eq!(pixel(&fb, 16, 25), 0);
+        assert_eq!(pixel(&fb, 15, 26), 0);
+    }
+
+    #[test]
+    fn test_display_image_flipped() {
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.display_image_scaled(&Coordinates::new(0, 0), &IMAGE, 2, 2, 1, true);
+        assert_eq!(
+            [
+                pixel(&fb, 0, 0),
+                pixel(&fb, 1, 0),
+                pixel(&fb, 0, 1),
+                pixel(&fb, 1, 1)
+            ],
+            [IMAGE[1], IMAGE[0], IMAGE[3], IMAGE[2]]
+        );
+    }
+
+    #[test]
+    fn test_display_image_transparency() {
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.draw_rect_fill(&Coordinates::new(0, 0), 4, 1, WHITE_COLOR);
+        let image = [TRANSPARENT_KEY, 0x00ff_0000, 0x8000_0000, 0xff00_00ff];
+        fb.display_image(&Coordinates::new(0, 0), &image, 4, 1);
+        assert_eq!(
+            [
+                pixel(&fb, 0, 0),
+                pixel(&fb, 1, 0),
+                pixel(&fb, 2, 0),
+                pixel(&fb, 3, 0)
+            ],
+            [
+                WHITE_COLOR.rgb(),
+                WHITE_COLOR.rgb(),
+                0xff7f_7f7f,
+                0xff00_00ff
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clipping() {
+        let mut fb = HeadlessFrameBuffer::new();
+        // partly off screen.
+        let bottom_right = Coordinates::new(SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1);
+        fb.display_image(&bottom_right, &IMAGE, 2, 2);
+        fb.draw_rect(bottom_right, 10, 10, WHITE_COLOR);
+        let mut top_left = Coordinates::new(0, 0);
+        top_left.set_virtual_x(-1.0);
+        top_left.sub_virtual_y(1.0, 1);
+        fb.display_image(&top_left, &IMAGE, 2, 2);
+        assert_eq!(pixel(&fb, 0, 0), IMAGE[3]);
+        assert_eq!(
+            pixel(&fb, SCREEN_WIDTH - 1, SCREEN_HEIGHT - 1),
+            WHITE_COLOR.rgb()
+        );
+
+        fb.set_clip(ClipRect::new(100, 100, 10, 10));
+        fb.draw_rect_fill(&Coordinates::new(95, 105), 10, 10, WHITE_COLOR);
+        assert_eq!(pixel(&fb, 99, 105), 0);
+        assert_eq!(pixel(&fb, 100, 105), WHITE_COLOR.rgb());
+        assert_eq!(pixel(&fb, 104, 109), WHITE_COLOR.rgb());
+        assert_eq!(pixel(&fb, 104, 110), 0);
+
+        // a row of opaque pixels cut on both sides.
+        fb.display_image(&Coordinates::new(99, 100), &IMAGE, 4, 1);
+        assert_eq!(pixel(&fb, 99, 100), 0);
+        assert_eq!(pixel(&fb, 100, 100), IMAGE[1]);
+        assert_eq!(pixel(&fb, 102, 100), IMAGE[3]);
+        fb.set_clip(ClipRect::new(100, 100, 2, 10));
+        fb.display_image(&Coordinates::new(99, 101), &IMAGE, 4, 1);
+        assert_eq!(
+            [pixel(&fb, 100, 101), pixel(&fb, 101, 101)],
+            [IMAGE[1], IMAGE[2]]
+        );
+        assert_eq!(pixel(&fb, 102, 101), 0);
+
+        // blending reads what's under, off screen there's nothing.
+        assert_eq!(fb.read_pixel(SCREEN_WIDTH as usize, 0), 0);
+        assert_eq!(fb.read_pixel(0, SCREEN_HEIGHT as usize), 0);
+    }
+
+    #[test]
+    fn test_draw_text() {
+        let background = Color::new(0, 0, 255);
+        let style = TextStyle::new(WHITE_COLOR)
+            .with_background(background)
+            .with_align(TextAlign::Right);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.draw_text("ab", Coordinates::new(100, 100), style);
+        let left = 100 - style.width("ab");
+        assert_eq!(pixel(&fb, left - 1, 100), 0);
+        assert_eq!(pixel(&fb, left, 100), background.rgb());
+        assert_eq!(
+            pixel(&fb, 99, 100 + style.letter_height() - 1),
+            background.rgb()
+        );
+        assert_eq!(pixel(&fb, 100, 100), 0);
+        let drawn = fb
+            .buffer()
+            .iter()
+            .filter(|p| **p == WHITE_COLOR.rgb())
+            .count();
+        assert!(drawn > 0);
+
+        // no background: only the letters are drawn.
+        let mut plain = HeadlessFrameBuffer::new();
+        plain.draw_text(
+            "ab",
+            Coordinates::new(left, 100),
+            TextStyle::new(WHITE_COLOR),
+        );
+        assert!(plain
+            .buffer()
+            .iter()
+            .all(|p| *p == 0 || *p == WHITE_COLOR.rgb()));
+        assert_eq!(
+            plain
+                .buffer()
+                .iter()
+                .filter(|p| **p == WHITE_COLOR.rgb())
+                .count(),
+            drawn
+        );
+    }
+
+    #[test]
+    fn test_draw_text_fallback() {
+        let style = TextStyle::new(WHITE_COLOR);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.draw_text("a\u{263a}", Coordinates::new(10, 10), style);
+        let mut expected = HeadlessFrameBuffer::new();
+        expected.draw_text("a?", Coordinates::new(10, 10), style);
+        assert_eq!(fb.buffer(), expected.buffer());
+
+        // no fallback either: left blank.
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.draw_text(
+            "\u{263a}a",
+            Coordinates::new(0, 10),
+            style.with_fallback('\u{263a}'),
+        );
+        let mut expected = HeadlessFrameBuffer::new();
+        expected.draw_text("a", Coordinates::new(style.letter_width(), 10), style);
+        assert_eq!(fb.buffer(), expected.buffer());
+    }
+}
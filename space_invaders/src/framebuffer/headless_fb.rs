@@ -0,0 +1,119 @@
+use crate::framebuffer::clip::ClipRect;
+use crate::framebuffer::fb_trait::FrameBufferInterface;
+use crate::{KeyPressedKeys, UserInput, SCREEN_HEIGHT, SCREEN_WIDTH};
+use std::collections::VecDeque;
+
+/// An in-memory framebuffer: nothing is shown on screen and the input is scripted.
+/// Every call to `get_input` (i.e. every game loop iteration) consumes one entry of the script,
+/// once the script is over no key is pressed anymore.
+pub struct HeadlessFrameBuffer {
+    buffer: Vec<u32>,
+    // the buffer shown, when double buffered: `buffer` is drawn while it's shown.
+    front: Option<Vec<u32>>,
+    width: u32,
+    height: u32,
+    clip: ClipRect,
+    script: VecDeque<Vec<KeyPressedKeys>>,
+    frames_drawn: usize,
+}
+
+impl Default for HeadlessFrameBuffer {
+    fn default() -> Self {
+        Self::with_size(SCREEN_WIDTH, SCREEN_HEIGHT)
+    }
+}
+
+impl HeadlessFrameBuffer {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A `width` x `height` screen, the playfield is scaled to fit it.
+    #[must_use]
+    pub fn with_size(width: u32, height: u32) -> Self {
+        Self {
+            buffer: vec![0; (width * height) as usize],
+            front: None,
+            width,
+            height,
+            clip: ClipRect::FULL,
+            script: VecDeque::new(),
+            frames_drawn: 0,
+        }
+    }
+
+    /// Draws in a buffer while showing the other one, like the Raspberry Pi's.
+    #[must_use]
+    pub fn double_buffered(mut self) -> Self {
+        self.front = Some(self.buffer.clone());
+        self
+    }
+
+    /// Queue the keys pressed during the next, not yet scripted, loop iteration.
+    pub fn push_input(&mut self, keys: &[KeyPressedKeys]) {
+        self.script.push_back(keys.to_vec());
+    }
+
+    /// Queue `frames` loop iterations without any key pressed.
+    pub fn push_idle(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.script.push_back(Vec::new());
+        }
+    }
+
+    /// When double buffered, the one shown.
+    #[must_use]
+    pub fn buffer(&self) -> &[u32] {
+        self.front.as_ref().unwrap_or(&self.buffer)
+    }
+
+    /// How many times `update` was called.
+    #[must_use]
+    pub fn frames_drawn(&self) -> usize {
+        self.frames_drawn
+    }
+}
+
+impl UserInput for HeadlessFrameBuffer {
+    fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys> {
+        self.script.pop_front().unwrap_or_default().into_iter()
+    }
+}
+
+impl FrameBufferInterface for HeadlessFrameBuffer {
+    fn raw_buffer(&mut self) -> &mut [u32] {
+        &mut self.buffer
+    }
+
+    fn width_u32(&self) -> u32 {
+        self.width
+    }
+
+    fn height_u32(&self) -> u32 {
+        self.height
+    }
+
+    fn clip(&self) -> ClipRect {
+        self.clip
+    }
+
+    fn set_clip(&mut self, clip: ClipRect) {
+        self.clip = clip;
+    }
+
+    fn buffer_count(&self) -> usize {
+        if self.front.is_some() {
+            2
+        } else {
+            1
+        }
+    }
+
+    fn update(&mut self) {
+        if let Some(front) = &mut self.front {
+            std::mem::swap(front, &mut self.buffer);
+        }
+        self.frames_drawn += 1;
+    }
+}
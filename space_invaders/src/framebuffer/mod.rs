@@ -1,12 +1,21 @@
 pub use color::Color;
 pub use coordinates::Coordinates;
 
+pub mod clip;
 pub mod color;
 
 pub mod coordinates;
 
+pub(crate) mod dirty;
+
 pub mod fb_trait;
 #[cfg(feature = "std")]
+pub mod headless_fb;
+#[cfg(feature = "std")]
 pub mod std_fb;
+pub mod text;
+pub mod viewport;
+#[cfg(feature = "std")]
+pub use headless_fb::HeadlessFrameBuffer;
 #[cfg(feature = "std")]
 pub use std_fb::StdFrameBuffer;
@@ -1,22 +1,34 @@
+use crate::framebuffer::clip::ClipRect;
 use crate::framebuffer::fb_trait::FrameBufferInterface;
 use crate::{KeyPressedKeys, UserInput, SCREEN_HEIGHT, SCREEN_WIDTH};
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
 
 pub struct StdFrameBuffer {
     pub(crate) window: Window,
     buffer: Vec<u32>,
+    clip: ClipRect,
 }
 impl UserInput for StdFrameBuffer {
     fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys> {
         let keys = self.window.get_keys();
+        // these only count once per press, so holding them doesn't e.g. pause and resume.
+        let pressed = self.window.get_keys_pressed(KeyRepeat::No);
         self.window.update();
-        keys.into_iter().filter_map(|key| match key {
+        let held = keys.into_iter().filter_map(|key| match key {
             Key::A | Key::Left => Some(KeyPressedKeys::Left),
             Key::D | Key::Right => Some(KeyPressedKeys::Right),
             Key::R => Some(KeyPressedKeys::Restart),
             Key::Space => Some(KeyPressedKeys::Shoot),
             _ => None,
-        })
+        });
+        let pressed = pressed.into_iter().filter_map(|key| match key {
+            Key::Enter => Some(KeyPressedKeys::Start),
+            Key::P => Some(KeyPressedKeys::Pause),
+            Key::F => Some(KeyPressedKeys::Debug),
+            Key::Q | Key::Escape => Some(KeyPressedKeys::Quit),
+            _ => None,
+        });
+        held.chain(pressed)
     }
 }
 impl Default for StdFrameBuffer {
@@ -36,7 +48,11 @@ impl Default for StdFrameBuffer {
         window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
         window.set_title("BareMetal Space Invaders");
 
-        StdFrameBuffer { window, buffer }
+        StdFrameBuffer {
+            window,
+            buffer,
+            clip: ClipRect::FULL,
+        }
     }
 }
 impl StdFrameBuffer {
@@ -50,6 +66,14 @@ impl FrameBufferInterface for StdFrameBuffer {
         &mut self.buffer
     }
 
+    fn clip(&self) -> ClipRect {
+        self.clip
+    }
+
+    fn set_clip(&mut self, clip: ClipRect) {
+        self.clip = clip;
+    }
+
     fn update(&mut self) {
         self.window
             .update_with_buffer(&self.buffer, SCREEN_WIDTH as usize, SCREEN_HEIGHT as usize)
@@ -0,0 +1,180 @@
+//! How text is drawn by `FrameBufferInterface::draw_text`, with the Noto Sans Mono bitmap font.
+
+use crate::framebuffer::color::{Color, WHITE_COLOR};
+use noto_sans_mono_bitmap::{
+    get_raster, get_raster_width, FontWeight, RasterHeight, RasterizedChar,
+};
+
+/// The letter heights the font is built with, see the `noto-sans-mono-bitmap` features.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextSize {
+    /// 16 pixels.
+    Small,
+    /// 20 pixels.
+    Medium,
+    /// 32 pixels.
+    Large,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextWeight {
+    Regular,
+    Bold,
+}
+
+/// Where the text is, horizontally, from the position it's drawn at.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextAlign {
+    /// Starts there.
+    Left,
+    /// Is centered there.
+    Center,
+    /// Ends there.
+    Right,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct TextStyle {
+    pub size: TextSize,
+    pub weight: TextWeight,
+    pub color: Color,
+    /// Fills the whole line behind the letters, nothing is drawn there when None.
+    pub background: Option<Color>,
+    pub align: TextAlign,
+    /// Drawn instead of the chars the font doesn't have.
+    pub fallback: char,
+}
+
+impl TextStyle {
+    /// `color` text, medium, regular, left aligned and without background.
+    #[must_use]
+    pub const fn new(color: Color) -> Self {
+        Self {
+            size: TextSize::Medium,
+            weight: TextWeight::Regular,
+            color,
+            background: None,
+            align: TextAlign::Left,
+            fallback: '?',
+        }
+    }
+
+    #[must_use]
+    pub const fn with_size(self, size: TextSize) -> Self {
+        Self { size, ..self }
+    }
+
+    #[must_use]
+    pub const fn with_weight(self, weight: TextWeight) -> Self {
+        Self { weight, ..self }
+    }
+
+    #[must_use]
+    pub const fn with_color(self, color: Color) -> Self {
+        Self { color, ..self }
+    }
+
+    #[must_use]
+    pub const fn with_background(self, background: Color) -> Self {
+        Self {
+            background: Some(background),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub const fn with_align(self, align: TextAlign) -> Self {
+        Self { align, ..self }
+    }
+
+    #[must_use]
+    pub const fn with_fallback(self, fallback: char) -> Self {
+        Self { fallback, ..self }
+    }
+
+    /// Every letter is as wide, the font is monospace.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // a few dozen pixels at most.
+    pub const fn letter_width(&self) -> u32 {
+        get_raster_width(self.font_weight(), self.raster_height()) as u32
+    }
+
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)] // a few dozen pixels at most.
+    pub const fn letter_height(&self) -> u32 {
+        self.raster_height().val() as u32
+    }
+
+    /// How wide `text` is drawn.
+    #[must_use]
+    pub fn width(&self, text: &str) -> u32 {
+        let len = u32::try_from(text.chars().count()).unwrap_or(u32::MAX);
+        len.saturating_mul(self.letter_width())
+    }
+
+    /// Where `text` starts when drawn at `x`.
+    pub(crate) fn left(&self, text: &str, x: i32) -> i32 {
+        let width = self.width(text);
+        match self.align {
+            TextAlign::Left => x,
+            TextAlign::Center => x.saturating_sub_unsigned(width / 2),
+            TextAlign::Right => x.saturating_sub_unsigned(width),
+        }
+    }
+
+    /// The letter for `c`, or the fallback one. None if the font has neither: nothing is drawn.
+    pub(crate) fn raster(&self, c: char) -> Option<RasterizedChar> {
+        let (weight, height) = (self.font_weight(), self.raster_height());
+        get_raster(c, weight, height).or_else(|| get_raster(self.fallback, weight, height))
+    }
+
+    const fn font_weight(&self) -> FontWeight {
+        match self.weight {
+            TextWeight::Regular => FontWeight::Regular,
+            TextWeight::Bold => FontWeight::Bold,
+        }
+    }
+
+    const fn raster_height(&self) -> RasterHeight {
+        match self.size {
+            TextSize::Small => RasterHeight::Size16,
+            TextSize::Medium => RasterHeight::Size20,
+            TextSize::Large => RasterHeight::Size32,
+        }
+    }
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self::new(WHITE_COLOR)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TextAlign, TextSize, TextStyle};
+
+    #[test]
+    fn test_alignment() {
+        let style = TextStyle::default();
+        let width = i32::try_from(style.width("abcd")).unwrap();
+        assert_eq!(style.left("abcd", 100), 100);
+        assert_eq!(
+            style.with_align(TextAlign::Center).left("abcd", 100),
+            100 - width / 2
+        );
+        assert_eq!(
+            style.with_align(TextAlign::Right).left("abcd", 100),
+            100 - width
+        );
+    }
+
+    #[test]
+    fn test_sizes() {
+        let style = TextStyle::default();
+        assert_eq!(style.letter_height(), 20);
+        assert_eq!(style.with_size(TextSize::Small).letter_height(), 16);
+        assert_eq!(style.with_size(TextSize::Large).letter_height(), 32);
+        assert!(style.with_size(TextSize::Large).letter_width() > style.letter_width());
+    }
+}
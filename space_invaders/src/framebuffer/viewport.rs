@@ -0,0 +1,104 @@
+use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+/// Where the playfield, `SCREEN_WIDTH` x `SCREEN_HEIGHT`, is drawn on the actual screen.
+/// It's scaled to fill as much of the screen as possible, keeping its aspect ratio, and centered:
+/// the bands left on the sides (or on top and bottom) stay black.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Viewport {
+    // the scale is scale_num / scale_den.
+    scale_num: u32,
+    scale_den: u32,
+    offset_x: u32,
+    offset_y: u32,
+}
+
+impl Viewport {
+    /// The playfield in a `width` x `height` screen.
+    #[must_use]
+    pub fn fit(width: u32, height: u32) -> Self {
+        // the smaller of width / SCREEN_WIDTH and height / SCREEN_HEIGHT.
+        let (scale_num, scale_den) = if u64::from(width) * u64::from(SCREEN_HEIGHT)
+            <= u64::from(height) * u64::from(SCREEN_WIDTH)
+        {
+            (width, SCREEN_WIDTH)
+        } else {
+            (height, SCREEN_HEIGHT)
+        };
+        let mut viewport = Self {
+            scale_num,
+            scale_den,
+            offset_x: 0,
+            offset_y: 0,
+        };
+        viewport.offset_x = width.saturating_sub(viewport.scale(SCREEN_WIDTH)) / 2;
+        viewport.offset_y = height.saturating_sub(viewport.scale(SCREEN_HEIGHT)) / 2;
+        viewport
+    }
+
+    /// Drawing happens pixel by pixel, without any scaling.
+    #[must_use]
+    pub const fn is_identity(&self) -> bool {
+        self.scale_num == self.scale_den && self.offset_x == 0 && self.offset_y == 0
+    }
+
+    fn scale(&self, value: u32) -> u32 {
+        let scaled = u64::from(value) * u64::from(self.scale_num) / u64::from(self.scale_den);
+        u32::try_from(scaled).expect("Conversion failed")
+    }
+
+    /// The screen column of the playfield's `x`.
+    #[must_use]
+    pub fn x(&self, x: u32) -> usize {
+        (self.offset_x + self.scale(x)) as usize
+    }
+
+    /// The screen row of the playfield's `y`.
+    #[must_use]
+    pub fn y(&self, y: u32) -> usize {
+        (self.offset_y + self.scale(y)) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Viewport;
+    use crate::{SCREEN_HEIGHT, SCREEN_WIDTH};
+
+    #[test]
+    fn test_same_size_is_identity() {
+        let viewport = Viewport::fit(SCREEN_WIDTH, SCREEN_HEIGHT);
+        assert!(viewport.is_identity());
+        assert_eq!(viewport.x(123), 123);
+        assert_eq!(viewport.y(SCREEN_HEIGHT), SCREEN_HEIGHT as usize);
+    }
+
+    #[test]
+    fn test_scaled_up() {
+        let viewport = Viewport::fit(1920, 1080);
+        assert!(!viewport.is_identity());
+        assert_eq!((viewport.x(0), viewport.y(0)), (0, 0));
+        assert_eq!(viewport.x(SCREEN_WIDTH), 1920);
+        assert_eq!(viewport.y(SCREEN_HEIGHT), 1080);
+        assert_eq!(viewport.x(1), 1);
+        assert_eq!(viewport.x(2), 3);
+    }
+
+    #[test]
+    fn test_letterboxed() {
+        // 4:3, black bands on top and bottom.
+        let viewport = Viewport::fit(640, 480);
+        assert_eq!((viewport.x(0), viewport.y(0)), (0, 60));
+        assert_eq!(
+            (viewport.x(SCREEN_WIDTH), viewport.y(SCREEN_HEIGHT)),
+            (640, 420)
+        );
+
+        // wider than 16:9, black bands on the sides.
+        let viewport = Viewport::fit(1920, 720);
+        assert_eq!((viewport.x(0), viewport.y(0)), (320, 0));
+        assert_eq!(
+            (viewport.x(SCREEN_WIDTH), viewport.y(SCREEN_HEIGHT)),
+            (1600, 720)
+        );
+    }
+}
@@ -1,37 +1,51 @@
 use crate::actor::{
-    Actor, Barricade, Enemies, Hero, HeroMovementDirection, LivesCount, ScoreCount, Shoots,
+    Actor, Barricade, EffectKind, Effects, Enemies, Hero, HeroMovementDirection, LivesCount,
+    ScoreCount, Shoots, Ufo, HERO_DEATH_MS, TOTAL_BARRICADES, TOTAL_ENEMIES,
+};
+use crate::debug_overlay::{ActorCounts, DebugOverlay};
+use crate::framebuffer::color::CLEAR_COLOR;
+use crate::framebuffer::dirty::{BoundsRecorder, DirtyFrameBuffer, DirtyTracker};
+use crate::EndOfGame::{Lost, Paused, Quit, Restarted, Won};
+use crate::{
+    Coordinates, EndOfGame, FrameBufferInterface, Level, RandomGenerator, TimeManagerInterface,
+    UserInput, FRAME_DURATION,
 };
-use crate::EndOfGame::{Lost, Restarted, Won};
-#[cfg(feature = "std")]
-use crate::FPS;
-use crate::{EndOfGame, FrameBufferInterface, TimeManagerInterface, UserInput};
 use core::ops::Sub;
 use core::time::Duration;
 use log::info;
 
-pub struct GameContext<'a, T, F>
+pub struct GameContext<'a, T, F, R>
 where
     F: FrameBufferInterface + UserInput,
     T: TimeManagerInterface,
+    R: RandomGenerator,
 {
     pub hero: Hero,
     pub time_manager: &'a T,
     fb: &'a mut F,
+    level: Level,
     shoots: Shoots,
-    barricades: [Barricade; 56],
+    barricades: [Barricade; TOTAL_BARRICADES],
     barricades_alive: usize,
     last_loop: Duration,
+    last_draw_loop: Duration,
     enemies: Enemies,
-    random: [u32; 20],
-    random_index: usize,
+    ufo: Ufo,
+    effects: Effects,
+    // while the hero death sequence plays, how long until it's over.
+    respawn_in_ms: u64,
+    rng: &'a mut R,
     lives_count: LivesCount,
     score_count: ScoreCount,
+    dirty: DirtyTracker,
+    debug: DebugOverlay,
 }
 
-impl<'a, T, F> GameContext<'a, T, F>
+impl<'a, T, F, R> GameContext<'a, T, F, R>
 where
     F: FrameBufferInterface + UserInput,
     T: TimeManagerInterface,
+    R: RandomGenerator,
 {
     pub fn new(
         fb: &'a mut F,
@@ -39,62 +53,93 @@ where
         current_score: u32,
         time_manager: &'a T,
         current_lives: u8,
+        level: Level,
+        rng: &'a mut R,
     ) -> Self {
-        let enemies = Enemies::new();
-        let shoots = Shoots::new();
+        let enemies = Enemies::new(level);
+        let shoots = Shoots::new(level);
         let hero = Hero::default();
 
         let barricades = Barricade::create_barricades();
         let barricades_alive = barricades.len();
-        let score_count = ScoreCount::new(current_score, high_score);
+        let score_count = ScoreCount::new(current_score, high_score, level.wave());
         let lives_count = LivesCount::new(current_lives);
 
         let last_loop = time_manager.now();
 
-        // super fast random :D
-        let random = [
-            35, 13, 65, 16, 15, 23, 84, 79, 65, 85, 99, 8, 63, 74, 57, 75, 9, 92, 25, 29,
-        ];
-        let random_index = 0;
-
         Self {
             hero,
             time_manager,
             fb,
+            level,
             shoots,
             barricades,
             barricades_alive,
             last_loop,
+            last_draw_loop: last_loop,
             enemies,
-            random,
-            random_index,
+            ufo: Ufo::default(),
+            effects: Effects::new(),
+            respawn_in_ms: 0,
+            rng,
             lives_count,
             score_count,
+            dirty: DirtyTracker::new(),
+            debug: DebugOverlay::new(),
         }
     }
 
     pub fn play(&mut self) -> EndOfGame {
-        let mut last_draw_loop: Duration = self.time_manager.now();
         loop {
-            let now = self.time_manager.now();
-            let delta_ms =
-                u64::try_from(now.sub(self.last_loop).as_millis()).expect("Conversion failed");
-            self.last_loop = now;
-            if self.random_index == self.random.len() {
-                self.random_index = 0;
+            if let Some(ret) = self.step() {
+                return ret;
             }
-            let rnd = self.random[self.random_index];
-            self.random_index += 1;
+            self.time_manager
+                .wait_for_tick(self.last_loop, FRAME_DURATION);
+        }
+    }
 
-            // 1. Get input
-            let (hero_movement_direction, shoot) =
-                self.fb.get_input_keys(&self.hero.structure.coordinates);
+    /// Runs a single iteration of the game loop: input, movement, collisions and, if enough
+    /// time has passed since the last one, a redraw.
+    /// Returns `Some` once the game is over.
+    ///
+    /// # Panics
+    /// If the time manager goes backwards.
+    pub fn step(&mut self) -> Option<EndOfGame> {
+        let now = self.time_manager.now();
+        let delta_ms = self.fb.frame_delta_ms(
+            u64::try_from(now.sub(self.last_loop).as_millis()).expect("Conversion failed"),
+        );
+        self.last_loop = now;
+        let rnd = self.rng.next_u32();
 
-            if matches!(hero_movement_direction, HeroMovementDirection::RestartGame) {
+        // 1. Get input
+        let (hero_movement_direction, shoot, toggle_debug) =
+            self.fb.get_input_keys(&self.hero.structure.coordinates);
+        if toggle_debug {
+            self.debug.toggle();
+        }
+
+        match hero_movement_direction {
+            HeroMovementDirection::RestartGame => {
                 info!("Restarting game...");
-                return Restarted;
+                return Some(Restarted);
             }
+            HeroMovementDirection::PauseGame => return Some(Paused),
+            HeroMovementDirection::QuitGame => return Some(Quit),
+            _ => {}
+        }
 
+        self.effects.update(delta_ms);
+        if self.respawn_in_ms > 0 {
+            // the hero got shot: everything stands still while it explodes.
+            self.respawn_in_ms = self.respawn_in_ms.saturating_sub(delta_ms);
+            if self.respawn_in_ms == 0 {
+                if let Some(ret) = self.respawn_hero() {
+                    return Some(ret);
+                }
+            }
+        } else {
             // 2. Handle shoots. Create if hero's or enemies' as needed.
             self.shoots.create_shoots(shoot, rnd, &mut self.enemies);
 
@@ -105,73 +150,137 @@ where
             self.shoots.check_collisions(
                 &mut self.hero,
                 &mut self.enemies,
+                &mut self.ufo,
                 &mut self.barricades,
                 &mut self.barricades_alive,
+                &mut self.effects,
             );
-            self.score_count.update(self.enemies.enemies_dead);
+            if self.score_count.update(self.round_score()) {
+                info!("Bonus life!");
+                self.lives_count.increase();
+            }
+
+            if !self.hero.is_alive() {
+                self.effects
+                    .spawn(EffectKind::HeroDeath, &self.hero.structure);
+                self.respawn_in_ms = HERO_DEATH_MS;
+            }
 
             // check if game is over.
             if let Some(ret) = self.check_game_over() {
-                return ret;
-            }
-            #[cfg(feature = "no_std")]
-            if now.sub(last_draw_loop).as_millis() >= 1000 / crate::FPS {
-                info!(
-                    "delta since last draw: {}",
-                    self.time_manager.since(last_draw_loop).as_millis()
-                );
-                last_draw_loop = now;
-
-                // Draw things:
-                self.fb.clear_screen();
-                self.draw();
-                self.fb.update();
+                return Some(ret);
             }
+        }
+        self.debug.record_update(self.time_manager.since(now));
+        #[cfg(feature = "no_std")]
+        if now.sub(self.last_draw_loop).as_millis() >= 1000 / crate::FPS {
+            let since_last = self.time_manager.since(self.last_draw_loop);
+            self.last_draw_loop = now;
+            let draw_started = self.time_manager.now();
+            self.render();
+            self.debug
+                .record_frame(since_last, self.time_manager.since(draw_started));
+        }
+        None
+    }
+
+    /// Gives access to the frame buffer while the game is running, e.g. to queue more input.
+    pub fn frame_buffer(&mut self) -> &mut F {
+        self.fb
+    }
 
-            #[cfg(feature = "std")]
-            let delta_next = Duration::from_millis(1000 / FPS as u64)
-                .saturating_sub(self.time_manager.since(self.last_loop));
-            #[cfg(feature = "std")]
-            if delta_next.as_millis() > 0 {
-                #[cfg(feature = "std")]
-                std::thread::sleep(delta_next);
+    #[must_use]
+    pub fn lives(&self) -> u8 {
+        self.lives_count.count
+    }
+
+    #[must_use]
+    pub fn enemies_dead(&self) -> usize {
+        self.enemies.enemies_dead
+    }
+
+    /// Draws a frame and shows it. Only what changed since the frame in the buffer drawn into
+    /// is cleared and drawn again.
+    pub(crate) fn render(&mut self) {
+        let mut bounds = BoundsRecorder::new();
+        self.split().1.draw(&mut bounds);
+        let dirty = self.dirty.next_frame(&bounds.rects, self.fb.buffer_count());
+        let (fb, scene) = self.split();
+        if let Some(dirty) = dirty {
+            for rect in dirty.iter() {
+                let top_left = Coordinates::new(rect.x, rect.y);
+                fb.draw_rect_fill(&top_left, rect.width, rect.height, CLEAR_COLOR);
             }
+            scene.draw(&mut DirtyFrameBuffer::new(fb, &dirty));
+        } else {
+            fb.clear_screen();
+            scene.draw(fb);
         }
+        fb.update();
     }
 
-    fn draw(&mut self) {
-        self.enemies.draw(self.fb);
-        self.hero.draw(self.fb);
-        self.shoots.draw(self.fb);
-        for b in self.barricades.iter().filter(|b| b.is_alive()) {
-            b.draw(self.fb);
-        }
-        self.score_count.draw(self.fb);
-        self.lives_count.draw(self.fb);
+    /// Same as `render`, with `overlay` drawn on top of the game. The whole frame is drawn.
+    pub(crate) fn render_with(&mut self, overlay: impl FnOnce(&mut F)) {
+        let (fb, scene) = self.split();
+        fb.clear_screen();
+        scene.draw(fb);
+        overlay(fb);
+        fb.update();
+        // the overlay isn't tracked.
+        self.dirty.invalidate();
+    }
+
+    /// Call before playing again after a pause: the time spent paused doesn't count.
+    pub fn resume(&mut self) {
+        self.last_loop = self.time_manager.now();
+    }
+
+    /// The frame buffer, and what's drawn on it.
+    fn split(&mut self) -> (&mut F, Scene<'_>) {
+        let scene = Scene {
+            enemies: &self.enemies,
+            ufo: &self.ufo,
+            hero: &self.hero,
+            shoots: &self.shoots,
+            barricades: &self.barricades,
+            effects: &self.effects,
+            score_count: &self.score_count,
+            lives_count: &self.lives_count,
+            debug: &self.debug,
+        };
+        (self.fb, scene)
     }
 
     fn handle_movements(&mut self, hero_movement_direction: HeroMovementDirection, delta_ms: u64) {
         self.shoots.handle_movement(delta_ms);
         self.enemies.move_enemies(delta_ms);
+        self.ufo.handle_movement(delta_ms, self.rng);
         self.hero.handle_movement(hero_movement_direction, delta_ms);
     }
 
-    /// It also check collision of aliens against barricades.
-    fn check_game_over(&mut self) -> Option<EndOfGame> {
-        if !self.hero.is_alive() {
-            if self.lives_count.is_out_of_lives() {
-                info!("Game over, you lost! You're out of lifes.");
-                return Some(Lost(self.enemies.enemies_dead));
-            }
-            self.lives_count.decrease();
-            //info!("Ouch! Lost a life, {} left", self.current_lifes);
-            self.shoots = Shoots::new();
-            self.hero.structure.alive = true;
+    /// Points earned in this game.
+    fn round_score(&self) -> u32 {
+        self.enemies.points + self.ufo.points
+    }
+
+    /// Once the hero death sequence is over, the hero is back if there are lives left.
+    fn respawn_hero(&mut self) -> Option<EndOfGame> {
+        if self.lives_count.is_out_of_lives() {
+            info!("Game over, you lost! You're out of lifes.");
+            return Some(Lost(self.round_score()));
         }
+        self.lives_count.decrease();
+        //info!("Ouch! Lost a life, {} left", self.current_lifes);
+        self.shoots = Shoots::new(self.level);
+        self.hero.structure.alive = true;
+        None
+    }
 
+    /// It also check collision of aliens against barricades.
+    fn check_game_over(&mut self) -> Option<EndOfGame> {
         if self.enemies.all_dead() {
             info!("Game over, you won! All enemies dead.",);
-            return Some(Won(self.enemies.enemies_dead));
+            return Some(Won(self.round_score()));
         }
 
         for enemy in self.enemies.enemies.iter().filter(|e| e.is_alive()) {
@@ -179,16 +288,242 @@ where
                 >= self.hero.get_coordinates().y();
             if reached_hero {
                 info!("Game over, you lost! Enemy has reached the hero");
-                return Some(Lost(self.enemies.enemies_dead));
+                return Some(Lost(self.round_score()));
             }
-            let reached_barricades = enemy.get_coordinates().y() + enemy.structure.height
-                >= self.barricades[0].get_coordinates().y();
-            if reached_barricades && self.barricades_alive > 0 {
-                for b in self.barricades.iter_mut() {
-                    b.structure.alive = false;
-                }
+            // enemies walking through the barricades eat them up.
+            for b in self.barricades.iter_mut().filter(|b| b.is_alive()) {
+                b.erase(&enemy.structure);
+                self.barricades_alive -= usize::from(!b.is_alive());
             }
         }
         None
     }
 }
+
+/// Everything drawn in a frame.
+struct Scene<'s> {
+    enemies: &'s Enemies,
+    ufo: &'s Ufo,
+    hero: &'s Hero,
+    shoots: &'s Shoots,
+    barricades: &'s [Barricade],
+    effects: &'s Effects,
+    score_count: &'s ScoreCount,
+    lives_count: &'s LivesCount,
+    debug: &'s DebugOverlay,
+}
+
+impl Scene<'_> {
+    fn draw(&self, fb: &mut impl FrameBufferInterface) {
+        self.enemies.draw(fb);
+        if self.ufo.is_alive() {
+            self.ufo.draw(fb);
+        }
+        if self.hero.is_alive() {
+            self.hero.draw(fb);
+        }
+        self.shoots.draw(fb);
+        for b in self.barricades.iter().filter(|b| b.is_alive()) {
+            b.draw(fb);
+        }
+        self.effects.draw(fb);
+        self.score_count.draw(fb);
+        self.lives_count.draw(fb);
+        if self.debug.is_visible() {
+            let counts = ActorCounts {
+                enemies: TOTAL_ENEMIES - self.enemies.enemies_dead,
+                shoots: self.shoots.flying(),
+                effects: self.effects.playing(),
+            };
+            self.debug.draw(fb, counts);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use crate::actor::{Actor, HERO_DEATH_MS};
+    use crate::screens;
+    use crate::{
+        EndOfGame, FrameBufferInterface, GameContext, GameState, HeadlessFrameBuffer,
+        KeyPressedKeys, Level, ManualClock, TimeManagerInterface, XorShift64Star, MAX_LIVES,
+        SCREEN_HEIGHT, SCREEN_WIDTH,
+    };
+    use core::time::Duration;
+
+    const FRAME: Duration = crate::FRAME_DURATION;
+    const SEED: u64 = 42;
+
+    #[test]
+    fn test_hero_moves_with_scripted_input() {
+        let clock = ManualClock::new(FRAME);
+        let mut rng = XorShift64Star::new(SEED);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.push_input(&[KeyPressedKeys::Left]);
+        fb.push_input(&[KeyPressedKeys::Left]);
+        fb.push_input(&[KeyPressedKeys::Right]);
+        {
+            let mut game =
+                GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+            let start_x = game.hero.get_coordinates().virtual_x;
+
+            clock.advance();
+            assert_eq!(game.step(), None);
+            clock.advance();
+            assert_eq!(game.step(), None);
+            let after_left = game.hero.get_coordinates().virtual_x;
+            assert!(after_left < start_x);
+
+            clock.advance();
+            assert_eq!(game.step(), None);
+            assert!(game.hero.get_coordinates().virtual_x > after_left);
+        }
+        assert_eq!(fb.frames_drawn(), 3);
+    }
+
+    #[test]
+    fn test_restart_key_ends_game() {
+        let clock = ManualClock::new(FRAME);
+        let mut rng = XorShift64Star::new(SEED);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.push_idle(5);
+        fb.push_input(&[KeyPressedKeys::Left, KeyPressedKeys::Restart]);
+        let mut game =
+            GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+        assert_eq!(game.play(), EndOfGame::Restarted);
+        assert_eq!(clock.now(), FRAME * 5);
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let clock = ManualClock::new(FRAME);
+        let mut rng = XorShift64Star::new(SEED);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.push_input(&[KeyPressedKeys::Pause]);
+        // released, pressed again to resume, then quit.
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Pause]);
+        fb.push_idle(2);
+        fb.push_input(&[KeyPressedKeys::Quit]);
+        let mut game =
+            GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+        assert_eq!(game.play(), EndOfGame::Paused);
+        assert_eq!(screens::paused(&mut game), GameState::Playing);
+        // a long pause doesn't make anything jump.
+        clock.advance_by(Duration::from_secs(10));
+        game.resume();
+        let start = game.enemies.enemies[0].get_coordinates().virtual_x;
+        assert_eq!(game.play(), EndOfGame::Quit);
+        let moved = game.enemies.enemies[0].get_coordinates().virtual_x - start;
+        assert!(moved.abs() < 10.0, "enemies moved {moved}px");
+    }
+
+    #[test]
+    fn test_hero_death_pauses_before_respawning() {
+        let clock = ManualClock::new(FRAME);
+        let mut rng = XorShift64Star::new(SEED);
+        let mut fb = HeadlessFrameBuffer::new();
+        let mut game =
+            GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+        game.hero.structure.alive = false;
+        clock.advance();
+        assert_eq!(game.step(), None);
+
+        let enemy_coordinates = *game.enemies.enemies[0].get_coordinates();
+        let mut paused_ms = FRAME;
+        while !game.hero.is_alive() {
+            assert_eq!(game.lives(), MAX_LIVES);
+            assert_eq!(
+                *game.enemies.enemies[0].get_coordinates(),
+                enemy_coordinates
+            );
+            clock.advance();
+            assert_eq!(game.step(), None);
+            paused_ms += FRAME;
+        }
+        assert!(paused_ms >= Duration::from_millis(HERO_DEATH_MS));
+        assert_eq!(game.lives(), MAX_LIVES - 1);
+    }
+
+    #[test]
+    fn test_simulation_is_deterministic() {
+        fn run() -> (Vec<Option<EndOfGame>>, u8, usize, Vec<u32>) {
+            let clock = ManualClock::new(FRAME);
+            let mut rng = XorShift64Star::new(SEED);
+            let mut fb = HeadlessFrameBuffer::new();
+            for _ in 0..10 {
+                fb.push_input(&[KeyPressedKeys::Shoot]);
+                fb.push_idle(5);
+                fb.push_input(&[KeyPressedKeys::Right]);
+            }
+            let mut game =
+                GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+            let mut results = Vec::new();
+            for _ in 0..100 {
+                clock.advance();
+                results.push(game.step());
+            }
+            let (lives, enemies_dead) = (game.lives(), game.enemies_dead());
+            (results, lives, enemies_dead, fb.buffer().to_vec())
+        }
+        assert_eq!(run(), run());
+    }
+
+    #[test]
+    fn test_dirty_rendering_matches_full_redraw() {
+        for fb in [
+            HeadlessFrameBuffer::new(),
+            HeadlessFrameBuffer::new().double_buffered(),
+        ] {
+            let clock = ManualClock::new(FRAME);
+            let mut rng = XorShift64Star::new(SEED);
+            let mut fb = fb;
+            for _ in 0..20 {
+                fb.push_input(&[KeyPressedKeys::Shoot, KeyPressedKeys::Left]);
+                fb.push_idle(3);
+            }
+            let mut game =
+                GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+            for _ in 0..120 {
+                clock.advance();
+                assert_eq!(game.step(), None);
+            }
+            let dirty = game.frame_buffer().buffer().to_vec();
+            game.render_with(|_| {});
+            assert!(
+                game.frame_buffer().buffer() == dirty,
+                "{} buffers",
+                game.frame_buffer().buffer_count()
+            );
+        }
+    }
+
+    #[test]
+    fn test_debug_key_toggles_overlay() {
+        // the bottom left corner, where only the overlay is drawn.
+        fn overlay_drawn(fb: &HeadlessFrameBuffer) -> bool {
+            let width = SCREEN_WIDTH as usize;
+            let bottom = width * (SCREEN_HEIGHT as usize - 50);
+            fb.buffer()[bottom..]
+                .chunks_exact(width)
+                .any(|row| row[..200].iter().any(|pixel| *pixel != 0))
+        }
+
+        let clock = ManualClock::new(FRAME);
+        let mut rng = XorShift64Star::new(SEED);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Debug]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Debug]);
+        let mut game =
+            GameContext::new(&mut fb, 0, 0, &clock, MAX_LIVES, Level::default(), &mut rng);
+        let mut drawn = Vec::new();
+        for _ in 0..4 {
+            clock.advance();
+            assert_eq!(game.step(), None);
+            drawn.push(overlay_drawn(game.frame_buffer()));
+        }
+        assert_eq!(drawn, [false, true, true, false]);
+    }
+}
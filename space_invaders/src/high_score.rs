@@ -0,0 +1,415 @@
+//! The best scores ever made, kept across games and power cycles by a `HighScoreStore`.
+//!
+//! ## Binary format
+//! | bytes | content                                                     |
+//! |-------|-------------------------------------------------------------|
+//! | 4     | magic, `b"SIHS"`                                            |
+//! | 1     | format version, currently `1`                               |
+//! | 1     | `n`, how many entries follow (at most `HIGH_SCORES_LEN`)    |
+//! | 7 * n | the entries, best first: 3 initials then the score, u32 little endian |
+//! | 1     | checksum, the wrapping sum of all the previous bytes        |
+//!
+//! It's always `HIGH_SCORES_MAX_BYTES` long at most, so it fits in a single disk sector.
+
+use log::{info, warn};
+
+pub const HIGH_SCORES_MAGIC: [u8; 4] = *b"SIHS";
+pub const HIGH_SCORES_VERSION: u8 = 1;
+/// How many scores the table keeps.
+pub const HIGH_SCORES_LEN: usize = 10;
+/// How many letters a player leaves next to their score.
+pub const INITIALS_LEN: usize = 3;
+
+const HEADER_LEN: usize = HIGH_SCORES_MAGIC.len() + 2;
+const ENTRY_LEN: usize = INITIALS_LEN + core::mem::size_of::<u32>();
+pub const HIGH_SCORES_MAX_BYTES: usize = HEADER_LEN + ENTRY_LEN * HIGH_SCORES_LEN + 1;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HighScoreError {
+    /// The data doesn't start with `HIGH_SCORES_MAGIC`.
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// More than `HIGH_SCORES_LEN` entries.
+    TooManyEntries(u8),
+    /// Initials must be upper case letters.
+    BadInitials,
+    BadChecksum,
+    Truncated,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HighScoreEntry {
+    /// Upper case ASCII letters.
+    pub initials: [u8; INITIALS_LEN],
+    pub score: u32,
+}
+
+impl HighScoreEntry {
+    #[must_use]
+    pub fn initials_str(&self) -> &str {
+        // only letters ever get in, see `valid_initials`.
+        core::str::from_utf8(&self.initials).unwrap_or("???")
+    }
+}
+
+fn valid_initials(initials: &[u8]) -> bool {
+    initials.iter().all(u8::is_ascii_uppercase)
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |sum, b| sum.wrapping_add(*b))
+}
+
+/// The top `HIGH_SCORES_LEN` scores, best first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct HighScoreTable {
+    entries: [Option<HighScoreEntry>; HIGH_SCORES_LEN],
+}
+
+impl HighScoreTable {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            entries: [None; HIGH_SCORES_LEN],
+        }
+    }
+
+    /// The best score, 0 if the table is empty.
+    #[must_use]
+    pub fn best(&self) -> u32 {
+        self.entries[0].map_or(0, |e| e.score)
+    }
+
+    /// True if `score` would make it into the table.
+    #[must_use]
+    pub fn qualifies(&self, score: u32) -> bool {
+        score > 0
+            && self
+                .entries
+                .iter()
+                .any(|e| e.is_none_or(|e| score > e.score))
+    }
+
+    /// Adds `entry` below the scores at least as good as it, dropping the last one if the table
+    /// is full. Returns where it ended up, `None` if it didn't make it.
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        if !self.qualifies(entry.score) {
+            return None;
+        }
+        let position = self
+            .entries
+            .iter()
+            .position(|e| e.is_none_or(|e| entry.score > e.score))?;
+        self.entries[position..].rotate_right(1);
+        self.entries[position] = Some(entry);
+        Some(position)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &HighScoreEntry> + '_ {
+        self.entries.iter().map_while(Option::as_ref)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.entries[0].is_none()
+    }
+
+    /// Writes the table to `out`, see the module docs for the format.
+    /// Returns how many bytes were written.
+    ///
+    /// # Panics
+    /// Never, the table can't have more than `HIGH_SCORES_LEN` entries.
+    pub fn to_bytes(&self, out: &mut [u8; HIGH_SCORES_MAX_BYTES]) -> usize {
+        out[..HIGH_SCORES_MAGIC.len()].copy_from_slice(&HIGH_SCORES_MAGIC);
+        out[HIGH_SCORES_MAGIC.len()] = HIGH_SCORES_VERSION;
+        out[HIGH_SCORES_MAGIC.len() + 1] = u8::try_from(self.len()).expect("Conversion failed");
+        let mut len = HEADER_LEN;
+        for entry in self.iter() {
+            out[len..len + INITIALS_LEN].copy_from_slice(&entry.initials);
+            out[len + INITIALS_LEN..len + ENTRY_LEN].copy_from_slice(&entry.score.to_le_bytes());
+            len += ENTRY_LEN;
+        }
+        out[len] = checksum(&out[..len]);
+        len + 1
+    }
+
+    /// Reads a table written by `to_bytes`, anything after it is ignored.
+    ///
+    /// # Errors
+    /// If `data` isn't a valid high score table.
+    ///
+    /// # Panics
+    /// Never, the entries are split in chunks of the right size.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, HighScoreError> {
+        if data.len() < HEADER_LEN {
+            return Err(HighScoreError::Truncated);
+        }
+        if data[..HIGH_SCORES_MAGIC.len()] != HIGH_SCORES_MAGIC {
+            return Err(HighScoreError::BadMagic);
+        }
+        let version = data[HIGH_SCORES_MAGIC.len()];
+        if version != HIGH_SCORES_VERSION {
+            return Err(HighScoreError::UnsupportedVersion(version));
+        }
+        let count = data[HIGH_SCORES_MAGIC.len() + 1];
+        if usize::from(count) > HIGH_SCORES_LEN {
+            return Err(HighScoreError::TooManyEntries(count));
+        }
+        let len = HEADER_LEN + usize::from(count) * ENTRY_LEN;
+        if data.len() <= len {
+            return Err(HighScoreError::Truncated);
+        }
+        if checksum(&data[..len]) != data[len] {
+            return Err(HighScoreError::BadChecksum);
+        }
+        let mut table = Self::new();
+        for (slot, bytes) in table
+            .entries
+            .iter_mut()
+            .zip(data[HEADER_LEN..len].chunks_exact(ENTRY_LEN))
+        {
+            let (initials, score) = bytes.split_at(INITIALS_LEN);
+            if !valid_initials(initials) {
+                return Err(HighScoreError::BadInitials);
+            }
+            *slot = Some(HighScoreEntry {
+                initials: initials.try_into().expect("Conversion failed"),
+                score: u32::from_le_bytes(score.try_into().expect("Conversion failed")),
+            });
+        }
+        // whatever order it was saved in, best first. Equal scores keep their order, the oldest
+        // first: an insertion sort, as `sort_by_key` needs `alloc`.
+        let score = |e: Option<HighScoreEntry>| e.map(|e| e.score);
+        for i in 1..usize::from(count) {
+            let mut j = i;
+            while j > 0 && score(table.entries[j - 1]) < score(table.entries[j]) {
+                table.entries.swap(j - 1, j);
+                j -= 1;
+            }
+        }
+        Ok(table)
+    }
+}
+
+/// Where the high score table is kept between runs.
+/// Failing to load gives an empty table, failing to save only gets logged: losing the scores
+/// shouldn't stop anyone from playing.
+pub trait HighScoreStore {
+    fn load(&mut self) -> HighScoreTable;
+    fn save(&mut self, table: &HighScoreTable);
+}
+
+/// Keeps the table only as long as the game runs, for when there's nowhere to save it.
+#[derive(Debug, Default)]
+pub struct MemoryHighScoreStore {
+    table: HighScoreTable,
+}
+
+impl MemoryHighScoreStore {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            table: HighScoreTable::new(),
+        }
+    }
+}
+
+impl HighScoreStore for MemoryHighScoreStore {
+    fn load(&mut self) -> HighScoreTable {
+        self.table
+    }
+
+    fn save(&mut self, table: &HighScoreTable) {
+        self.table = *table;
+    }
+}
+
+#[cfg(feature = "std")]
+pub use std_store::FileHighScoreStore;
+
+#[cfg(feature = "std")]
+mod std_store {
+    use super::{HighScoreStore, HighScoreTable, HIGH_SCORES_MAX_BYTES};
+    use log::{error, info, warn};
+    use std::path::PathBuf;
+
+    /// Keeps the table in a file, created on the first save.
+    pub struct FileHighScoreStore {
+        path: PathBuf,
+    }
+
+    impl FileHighScoreStore {
+        pub fn new(path: impl Into<PathBuf>) -> Self {
+            Self { path: path.into() }
+        }
+    }
+
+    impl HighScoreStore for FileHighScoreStore {
+        fn load(&mut self) -> HighScoreTable {
+            let data = match std::fs::read(&self.path) {
+                Ok(data) => data,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    info!("No high scores yet in {}", self.path.display());
+                    return HighScoreTable::new();
+                }
+                Err(e) => {
+                    error!("Failed to read {}: {e}", self.path.display());
+                    return HighScoreTable::new();
+                }
+            };
+            HighScoreTable::from_bytes(&data).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring invalid high scores in {}: {e:?}",
+                    self.path.display()
+                );
+                HighScoreTable::new()
+            })
+        }
+
+        fn save(&mut self, table: &HighScoreTable) {
+            let mut bytes = [0; HIGH_SCORES_MAX_BYTES];
+            let len = table.to_bytes(&mut bytes);
+            if let Err(e) = std::fs::write(&self.path, &bytes[..len]) {
+                error!("Failed to write {}: {e}", self.path.display());
+            }
+        }
+    }
+}
+
+/// Loads the table from `store`, logging what's in it.
+pub(crate) fn load_table(store: &mut impl HighScoreStore) -> HighScoreTable {
+    let table = store.load();
+    if table.is_empty() {
+        warn!("The high score table is empty");
+    }
+    for (i, entry) in table.iter().enumerate() {
+        info!("{:>2}. {} {}", i + 1, entry.initials_str(), entry.score);
+    }
+    table
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        checksum, HighScoreEntry, HighScoreError, HighScoreStore, HighScoreTable,
+        MemoryHighScoreStore, HIGH_SCORES_LEN, HIGH_SCORES_MAX_BYTES,
+    };
+
+    const fn entry(initials: [u8; 3], score: u32) -> HighScoreEntry {
+        HighScoreEntry { initials, score }
+    }
+
+    #[test]
+    fn test_table_keeps_the_best_first() {
+        let mut table = HighScoreTable::new();
+        assert_eq!(table.best(), 0);
+        assert!(!table.qualifies(0));
+        assert_eq!(table.insert(entry(*b"AAA", 100)), Some(0));
+        assert_eq!(table.insert(entry(*b"BBB", 300)), Some(0));
+        // a tie goes below the score that was there first.
+        assert_eq!(table.insert(entry(*b"CCC", 100)), Some(2));
+        assert_eq!(table.best(), 300);
+
+        for score in 1..=20 {
+            table.insert(entry(*b"ZZZ", score * 10));
+        }
+        assert_eq!(table.len(), HIGH_SCORES_LEN);
+        assert!(!table.qualifies(110));
+        assert_eq!(table.insert(entry(*b"DDD", 50)), None);
+        let scores: [u32; HIGH_SCORES_LEN] =
+            core::array::from_fn(|i| table.entries[i].unwrap().score);
+        assert_eq!(scores, [300, 200, 190, 180, 170, 160, 150, 140, 130, 120]);
+    }
+
+    #[test]
+    fn test_bytes_roundtrip() {
+        let mut table = HighScoreTable::new();
+        table.insert(entry(*b"ABC", u32::MAX));
+        table.insert(entry(*b"XYZ", 42));
+        // ties stay in the order they were made.
+        for initials in [*b"AAA", *b"BBB", *b"CCC", *b"DDD"] {
+            table.insert(entry(initials, 7));
+        }
+        let mut bytes = [0; HIGH_SCORES_MAX_BYTES];
+        let len = table.to_bytes(&mut bytes);
+        assert_eq!(HighScoreTable::from_bytes(&bytes[..len]), Ok(table));
+
+        let empty = HighScoreTable::new();
+        let len = empty.to_bytes(&mut bytes);
+        assert_eq!(HighScoreTable::from_bytes(&bytes[..len]), Ok(empty));
+    }
+
+    #[test]
+    fn test_load_sorts_and_keeps_ties() {
+        let mut bytes = [0; HIGH_SCORES_MAX_BYTES];
+        bytes[..6].copy_from_slice(b"SIHS\x01\x03");
+        for (i, (initials, score)) in [(b"AAA", 5_u32), (b"BBB", 9), (b"CCC", 5)]
+            .iter()
+            .enumerate()
+        {
+            let at = 6 + i * 7;
+            bytes[at..at + 3].copy_from_slice(*initials);
+            bytes[at + 3..at + 7].copy_from_slice(&score.to_le_bytes());
+        }
+        bytes[27] = checksum(&bytes[..27]);
+        let table = HighScoreTable::from_bytes(&bytes[..28]).unwrap();
+        let initials: [[u8; 3]; 3] = core::array::from_fn(|i| table.entries[i].unwrap().initials);
+        assert_eq!(initials, [*b"BBB", *b"AAA", *b"CCC"]);
+    }
+
+    #[test]
+    fn test_corrupted_bytes() {
+        let mut table = HighScoreTable::new();
+        table.insert(entry(*b"ABC", 1234));
+        let mut bytes = [0; HIGH_SCORES_MAX_BYTES];
+        let len = table.to_bytes(&mut bytes);
+
+        assert_eq!(
+            HighScoreTable::from_bytes(&bytes[..len - 1]),
+            Err(HighScoreError::Truncated)
+        );
+        bytes[7] ^= 1;
+        assert_eq!(
+            HighScoreTable::from_bytes(&bytes[..len]),
+            Err(HighScoreError::BadChecksum)
+        );
+        assert_eq!(
+            HighScoreTable::from_bytes(&[0; HIGH_SCORES_MAX_BYTES]),
+            Err(HighScoreError::BadMagic)
+        );
+    }
+
+    #[test]
+    fn test_memory_store() {
+        let mut store = MemoryHighScoreStore::new();
+        let mut table = store.load();
+        table.insert(entry(*b"ABC", 10));
+        store.save(&table);
+        assert_eq!(store.load(), table);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_file_store() {
+        use super::FileHighScoreStore;
+        let path = std::env::temp_dir().join(format!("high_scores_{}.dat", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut store = FileHighScoreStore::new(&path);
+        assert!(store.load().is_empty());
+
+        let mut table = HighScoreTable::new();
+        table.insert(entry(*b"ABC", 10));
+        store.save(&table);
+        assert_eq!(FileHighScoreStore::new(&path).load(), table);
+
+        // a broken file is ignored.
+        std::fs::write(&path, b"garbage").unwrap();
+        assert!(store.load().is_empty());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
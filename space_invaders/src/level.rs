@@ -0,0 +1,97 @@
+use crate::actor::SHOOT_ENEMY_MAX;
+
+// how many pixels lower the enemies start on every new wave.
+const WAVE_STEP_DOWN: u32 = 20;
+// don't start too close to the barricades.
+const MAX_OFFSET_Y: u32 = 120;
+
+// enemy shots on screen at once in the first wave, one more every wave.
+// the enemies shoot as soon as one is free, so it's their fire rate too.
+const FIRST_WAVE_ENEMY_SHOOTS: usize = 3;
+
+/// The wave being played: every wave won, the next one gets harder.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Level {
+    wave: u32,
+}
+
+impl Default for Level {
+    fn default() -> Self {
+        Self { wave: 1 }
+    }
+}
+
+impl Level {
+    /// Waves start from 1.
+    #[must_use]
+    pub const fn wave(self) -> u32 {
+        self.wave
+    }
+
+    #[must_use]
+    pub const fn next(self) -> Self {
+        Self {
+            wave: self.wave + 1,
+        }
+    }
+
+    // waves already won.
+    const fn waves_won(self) -> u32 {
+        self.wave - 1
+    }
+
+    /// How many pixels lower than usual the enemies start.
+    pub(crate) fn enemies_offset_y(self) -> u32 {
+        self.waves_won()
+            .saturating_mul(WAVE_STEP_DOWN)
+            .min(MAX_OFFSET_Y)
+    }
+
+    /// Multiplies the enemies' base speed.
+    pub(crate) fn enemy_speed_factor(self) -> f64 {
+        1.0 + f64::from(self.waves_won()) * 0.25
+    }
+
+    /// How many enemy shots can be on screen at once.
+    pub(crate) fn enemy_shoots_max(self) -> usize {
+        usize::try_from(self.waves_won())
+            .map_or(SHOOT_ENEMY_MAX, |won| {
+                FIRST_WAVE_ENEMY_SHOOTS.saturating_add(won)
+            })
+            .min(SHOOT_ENEMY_MAX)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Level, FIRST_WAVE_ENEMY_SHOOTS, MAX_OFFSET_Y};
+    use crate::actor::SHOOT_ENEMY_MAX;
+
+    #[test]
+    fn test_waves_get_harder() {
+        let first = Level::default();
+        let second = first.next();
+        assert_eq!((first.wave(), second.wave()), (1, 2));
+        assert_eq!(first.enemies_offset_y(), 0);
+        assert!(second.enemies_offset_y() > first.enemies_offset_y());
+        assert!(second.enemy_speed_factor() > first.enemy_speed_factor());
+        assert!(second.enemy_shoots_max() > first.enemy_shoots_max());
+    }
+
+    #[test]
+    fn test_first_wave_is_unchanged() {
+        let first = Level::default();
+        assert!((first.enemy_speed_factor() - 1.0).abs() < f64::EPSILON);
+        assert_eq!(first.enemy_shoots_max(), FIRST_WAVE_ENEMY_SHOOTS);
+    }
+
+    #[test]
+    fn test_difficulty_is_capped() {
+        let mut level = Level::default();
+        for _ in 0..100 {
+            level = level.next();
+        }
+        assert_eq!(level.enemies_offset_y(), MAX_OFFSET_Y);
+        assert_eq!(level.enemy_shoots_max(), SHOOT_ENEMY_MAX);
+    }
+}
@@ -4,69 +4,141 @@
 #![cfg_attr(feature = "no_std", feature(format_args_nl))]
 #![warn(clippy::pedantic)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
 extern crate core;
 
 pub mod actor;
+mod debug_overlay;
 mod framebuffer;
 
 mod game_context;
+mod high_score;
+mod level;
 mod platform;
+mod random;
+mod replay;
+mod screens;
+#[cfg(all(test, feature = "std"))]
+mod snapshot;
+mod text_buffer;
 mod time;
 
+use core::time::Duration;
 use log::info;
 
 #[cfg(feature = "std")]
 pub use crate::time::TimeManager;
 
-pub use crate::time::TimeManagerInterface;
+pub use crate::time::{ManualClock, TimeManagerInterface};
+
+pub use crate::game_context::GameContext;
+
+pub use crate::level::Level;
+
+#[cfg(feature = "std")]
+pub use crate::high_score::FileHighScoreStore;
+pub use crate::high_score::{
+    HighScoreEntry, HighScoreError, HighScoreStore, HighScoreTable, MemoryHighScoreStore,
+    HIGH_SCORES_LEN, HIGH_SCORES_MAGIC, HIGH_SCORES_MAX_BYTES, HIGH_SCORES_VERSION, INITIALS_LEN,
+};
+
+pub use crate::random::{RandomGenerator, XorShift64Star};
+
+pub use crate::text_buffer::TextBuffer;
+
+pub use crate::replay::{
+    FrameKeys, RecordedFrame, Recorder, RecordingSink, ReplayError, ReplayReader, Replayer,
+    SliceSink, MAX_KEYS_PER_FRAME, REPLAY_MAGIC, REPLAY_VERSION,
+};
 
 use crate::actor::{
     HeroMovementDirection, Shoot, ShootOwner, SHOOT_OFFSET_X_HERO, SHOOT_OFFSET_Y_HERO,
 };
 
+pub use crate::framebuffer::clip::ClipRect;
 pub use crate::framebuffer::fb_trait::FrameBufferInterface;
+pub use crate::framebuffer::text::{TextAlign, TextSize, TextStyle, TextWeight};
+pub use crate::framebuffer::viewport::Viewport;
 pub use crate::framebuffer::{Color, Coordinates};
 
 #[cfg(feature = "std")]
-pub use framebuffer::StdFrameBuffer;
+pub use framebuffer::{HeadlessFrameBuffer, StdFrameBuffer};
 
+/// The playfield the game is laid out in, scaled to the actual screen by `Viewport`.
 pub const SCREEN_WIDTH: u32 = 1280;
 pub const SCREEN_WIDTH_NO_MARGIN: u32 = SCREEN_WIDTH - SCREEN_MARGIN;
 pub const SCREEN_HEIGHT: u32 = 720;
 pub const SCREEN_HEIGHT_NO_MARGIN: u32 = SCREEN_HEIGHT - SCREEN_MARGIN;
 pub const SCREEN_MARGIN: u32 = 20;
 
-pub(crate) const MAX_LIVES: u8 = 3;
+pub const MAX_LIVES: u8 = 3;
+
+/// Reaching this score is worth an extra life, once per game.
+pub const BONUS_LIFE_SCORE: u32 = 1500;
 
 // todo: in STD, if FPS is very low (i.e. no sleep at the end of the loop) enemies are stopped
 // because the speedup rounds to 0.
 const FPS: u128 = 30;
+/// How long a frame lasts, at `FPS`.
+#[allow(clippy::cast_possible_truncation)] // FPS is small.
+const FRAME_DURATION: Duration = Duration::from_micros((1_000_000 / FPS) as u64);
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EndOfGame {
     Restarted,
-    Won(usize),
-    Lost(usize),
+    Won(u32),
+    Lost(u32),
+    /// Not over yet: calling `play` again resumes it.
+    Paused,
+    /// The player gave up, back to the title screen.
+    Quit,
 }
 impl EndOfGame {
     fn to_score(&self) -> u32 {
-        use EndOfGame::{Lost, Restarted, Won};
+        use EndOfGame::{Lost, Paused, Quit, Restarted, Won};
         match self {
-            Won(x) | Lost(x) => u32::try_from(*x).expect("Conversion failed"),
-            Restarted => 0,
+            Won(x) | Lost(x) => *x,
+            Restarted | Paused | Quit => 0,
         }
     }
 }
+
+/// What the game is showing, see `run_game`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameState {
+    /// Waiting for the player to start, with the high scores.
+    Title,
+    Playing,
+    /// Everything stands still until pause or start is pressed again.
+    Paused,
+    /// The game was lost with this score.
+    GameOver(u32),
+    /// The score made it into the high score table, asking for the initials.
+    HighScoreEntry(u32),
+}
+
 pub trait UserInput {
     fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys>;
 
-    // get input from keyboard
+    /// Called once per game loop iteration, before reading the input, with the milliseconds
+    /// passed since the previous iteration. The game will use the returned value instead:
+    /// this is where input sources that record or replay a session hook in.
+    fn frame_delta_ms(&mut self, delta_ms: u64) -> u64 {
+        delta_ms
+    }
+
+    // get input from keyboard, and whether the debug overlay is toggled.
     fn get_input_keys(
         &mut self,
         hero_coordinates: &Coordinates,
-    ) -> (HeroMovementDirection, Option<Shoot>) {
+    ) -> (HeroMovementDirection, Option<Shoot>, bool) {
         let mut hero_movement_direction = HeroMovementDirection::Still;
         let mut shoot = None;
+        let mut toggle_debug = false;
         let mut restart = None;
+        let mut pause = None;
+        let mut quit = None;
         for key in self.get_input() {
             match key {
                 KeyPressedKeys::Left => {
@@ -90,44 +162,146 @@ pub trait UserInput {
                     //info!("pressed restart");
                     restart = Some((HeroMovementDirection::RestartGame, None));
                 }
+                KeyPressedKeys::Pause => {
+                    pause = Some((HeroMovementDirection::PauseGame, None));
+                }
+                KeyPressedKeys::Quit => {
+                    quit = Some((HeroMovementDirection::QuitGame, None));
+                }
+                KeyPressedKeys::Debug => {
+                    toggle_debug = true;
+                }
+                // only used by the other screens.
+                KeyPressedKeys::Start => {}
             }
         }
-        if let Some(restart) = restart {
-            return restart;
+        if let Some((direction, shoot)) = quit.or(restart).or(pause) {
+            return (direction, shoot, toggle_debug);
         }
-        (hero_movement_direction, shoot)
+        (hero_movement_direction, shoot, toggle_debug)
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum KeyPressedKeys {
     Left,
     Right,
     Shoot,
     Restart,
+    /// Starts a game from the title screen, or resumes it.
+    Start,
+    /// Pauses or resumes the game.
+    Pause,
+    /// Leaves the game for the title screen, or the title screen for good.
+    Quit,
+    /// Shows or hides the frame timings and actor counts over the game.
+    Debug,
+}
+
+/// `seed` drives every random choice of the game: running again with the same seed and input
+/// (see `Recorder`) reproduces the same session.
+/// The best scores are loaded from `high_scores` at start and saved back every time a lost game
+/// makes it into the table.
+///
+/// It goes from the title screen to playing, which can be paused, to the game over screen and,
+/// with a good enough score, to the high score entry, then back to the title screen.
+/// Returns when quit is pressed on the title screen.
+pub fn run_game<F>(
+    mut fb: F,
+    time_manager: &impl TimeManagerInterface,
+    seed: u64,
+    mut high_scores: impl HighScoreStore,
+) where
+    F: FrameBufferInterface + UserInput,
+{
+    info!("Random seed: {seed}");
+    let mut rng = XorShift64Star::new(seed);
+    let mut table = high_score::load_table(&mut high_scores);
+    let mut state = GameState::Title;
+    loop {
+        info!("{state:?}");
+        state = match state {
+            GameState::Title => match screens::title(&mut fb, time_manager, &table) {
+                Some(next) => next,
+                None => return,
+            },
+            GameState::Playing | GameState::Paused => {
+                play(&mut fb, time_manager, &mut rng, table.best())
+            }
+            GameState::GameOver(score) => {
+                screens::game_over(&mut fb, time_manager, score);
+                if table.qualifies(score) {
+                    GameState::HighScoreEntry(score)
+                } else {
+                    GameState::Title
+                }
+            }
+            GameState::HighScoreEntry(score) => {
+                let initials = screens::enter_initials(&mut fb, time_manager, &table, score);
+                let entry = HighScoreEntry { initials, score };
+                if let Some(position) = table.insert(entry) {
+                    info!(
+                        "{} is number {} with {score}",
+                        entry.initials_str(),
+                        position + 1
+                    );
+                }
+                high_scores.save(&table);
+                GameState::Title
+            }
+        };
+    }
 }
 
-pub fn run_game<F>(mut fb: F, time_manager: &impl TimeManagerInterface)
+/// Plays wave after wave until the game is lost or quit, returns the state to go to next.
+fn play<F>(
+    fb: &mut F,
+    time_manager: &impl TimeManagerInterface,
+    rng: &mut XorShift64Star,
+    best_score: u32,
+) -> GameState
 where
     F: FrameBufferInterface + UserInput,
 {
-    let mut high_score = 0;
     let mut current_score: u32 = 0;
+    let mut level = Level::default();
+    let mut lives = MAX_LIVES;
     loop {
-        info!("Starting game...");
+        info!("Starting wave {}...", level.wave());
+        let high_score = best_score.max(current_score);
         let mut game_context = game_context::GameContext::new(
-            &mut fb,
+            fb,
             high_score,
             current_score,
             time_manager,
-            MAX_LIVES,
+            lives,
+            level,
+            rng,
         );
-        let result = game_context.play();
-        current_score += result.to_score();
-        if current_score > high_score {
-            high_score = current_score;
+        let mut result = game_context.play();
+        while result == EndOfGame::Paused {
+            if screens::paused(&mut game_context) == GameState::Title {
+                result = EndOfGame::Quit;
+            } else {
+                game_context.resume();
+                result = game_context.play();
+            }
         }
-        if matches!(result, EndOfGame::Lost(_)) {
-            current_score = 0;
+        let lives_left = game_context.lives();
+        current_score = current_score.saturating_add(result.to_score());
+        match result {
+            // winning a wave carries on to the next one.
+            EndOfGame::Won(_) => {
+                level = level.next();
+                lives = lives_left;
+            }
+            EndOfGame::Lost(_) => return GameState::GameOver(current_score),
+            EndOfGame::Restarted => {
+                current_score = 0;
+                level = Level::default();
+                lives = MAX_LIVES;
+            }
+            EndOfGame::Quit | EndOfGame::Paused => return GameState::Title,
         }
     }
 }
@@ -1,9 +1,72 @@
 use env_logger::Env;
 
-use space_invaders::{run_game, StdFrameBuffer};
+use space_invaders::{
+    run_game, FileHighScoreStore, MemoryHighScoreStore, Recorder, ReplayReader, Replayer,
+    StdFrameBuffer,
+};
+
+// next to where the game is started from, unless asked otherwise.
+const DEFAULT_HIGH_SCORES_PATH: &str = "high_scores.dat";
 
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
-    run_game(StdFrameBuffer::new(), &space_invaders::TimeManager::new());
+    let mut record = None;
+    let mut replay = None;
+    let mut seed = None;
+    let mut scores = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("Missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--record" => record = Some(value()),
+            "--replay" => replay = Some(value()),
+            "--scores" => scores = Some(value()),
+            "--seed" => {
+                seed = Some(
+                    value()
+                        .parse::<u64>()
+                        .unwrap_or_else(|e| panic!("Invalid seed: {e}")),
+                );
+            }
+            _ => panic!(
+                "Unknown argument {arg}, use --record <file>, --replay <file>, --seed <u64> or --scores <file>"
+            ),
+        }
+    }
+    // from the OS' entropy, unless asked otherwise.
+    let seed = seed.unwrap_or_else(rand::random);
+
+    let time_manager = space_invaders::TimeManager::new();
+    // whether the initials are asked for depends on the table: recordings start from an empty
+    // one, so they can be replayed anywhere.
+    if let Some(path) = replay {
+        let data = std::fs::read(&path).unwrap_or_else(|e| panic!("Failed to read {path}: {e}"));
+        let reader = ReplayReader::new(&data)
+            .unwrap_or_else(|e| panic!("{path} is not a valid recording: {e:?}"));
+        let seed = reader.seed();
+        run_game(
+            Replayer::new(StdFrameBuffer::new(), reader),
+            &time_manager,
+            seed,
+            MemoryHighScoreStore::new(),
+        );
+    } else if let Some(path) = record {
+        let file =
+            std::fs::File::create(&path).unwrap_or_else(|e| panic!("Failed to create {path}: {e}"));
+        // not buffered: the game is usually closed by killing it.
+        run_game(
+            Recorder::new(StdFrameBuffer::new(), file, seed),
+            &time_manager,
+            seed,
+            MemoryHighScoreStore::new(),
+        );
+    } else {
+        let high_scores =
+            FileHighScoreStore::new(scores.unwrap_or_else(|| DEFAULT_HIGH_SCORES_PATH.to_string()));
+        run_game(StdFrameBuffer::new(), &time_manager, seed, high_scores);
+    }
 }
@@ -0,0 +1,70 @@
+/// Source of randomness for the game, e.g. to pick which enemy is going to shoot.
+pub trait RandomGenerator {
+    fn next_u32(&mut self) -> u32;
+}
+
+/// xorshift64* (Marsaglia, Vigna): small, fast and good enough for a game.
+/// The same seed always produces the same sequence, so a run can be reproduced.
+#[derive(Debug, Clone)]
+pub struct XorShift64Star {
+    state: u64,
+}
+
+impl XorShift64Star {
+    // the state must never be 0, or the generator would only return zeroes.
+    const ZERO_SEED_REPLACEMENT: u64 = 0x9E37_79B9_7F4A_7C15;
+
+    #[must_use]
+    pub const fn new(seed: u64) -> Self {
+        let state = if seed == 0 {
+            Self::ZERO_SEED_REPLACEMENT
+        } else {
+            seed
+        };
+        Self { state }
+    }
+}
+
+impl RandomGenerator for XorShift64Star {
+    fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        // the high bits are the best ones.
+        u32::try_from(self.state.wrapping_mul(0x2545_F491_4F6C_DD1D) >> 32)
+            .expect("Conversion failed")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RandomGenerator, XorShift64Star};
+
+    #[test]
+    fn test_same_seed_same_sequence() {
+        let mut a = XorShift64Star::new(42);
+        let mut b = XorShift64Star::new(42);
+        let mut c = XorShift64Star::new(43);
+        let mut different = false;
+        for _ in 0..100 {
+            let next = a.next_u32();
+            assert_eq!(next, b.next_u32());
+            different |= next != c.next_u32();
+        }
+        assert!(different);
+    }
+
+    #[test]
+    fn test_zero_seed() {
+        let mut rng = XorShift64Star::new(0);
+        assert!((0..10).map(|_| rng.next_u32()).any(|n| n != 0));
+    }
+
+    #[test]
+    fn test_doesnt_repeat_like_the_old_table() {
+        let mut rng = XorShift64Star::new(1);
+        let first: [u32; 20] = core::array::from_fn(|_| rng.next_u32());
+        let second: [u32; 20] = core::array::from_fn(|_| rng.next_u32());
+        assert_ne!(first, second);
+    }
+}
@@ -0,0 +1,776 @@
+//! Recording and replay of game sessions.
+//!
+//! A `Recorder` wraps any input source and logs, for every game loop iteration, the time elapsed
+//! since the previous one and the keys pressed. A `Replayer` feeds a recording back into the
+//! game: since the enemies' fire pattern only depends on the random seed and on the number of
+//! loop iterations, this reproduces a session exactly.
+//!
+//! ## Binary format
+//! | bytes | content                                                     |
+//! |-------|-------------------------------------------------------------|
+//! | 4     | magic, `b"SIRP"`                                            |
+//! | 1     | format version, currently `3`                               |
+//! | 8     | the seed passed to `run_game`, u64 little endian            |
+//! | ...   | one record per loop iteration, until the end of the data    |
+//!
+//! Each record is:
+//!
+//! | bytes | content                                                     |
+//! |-------|-------------------------------------------------------------|
+//! | 1-10  | `delta_ms`, unsigned LEB128                                 |
+//! | 1     | `n`, how many keys were pressed (at most `MAX_KEYS_PER_FRAME`) |
+//! | n     | the keys, in the order they were reported: see `key_code`   |
+//!
+//! An iteration without any key pressed and less than 128ms long takes 2 bytes.
+
+use crate::{
+    ClipRect, Color, Coordinates, FrameBufferInterface, KeyPressedKeys, TextStyle, UserInput,
+    Viewport,
+};
+
+pub const REPLAY_MAGIC: [u8; 4] = *b"SIRP";
+pub const REPLAY_VERSION: u8 = 3;
+const SEED_OFFSET: usize = REPLAY_MAGIC.len() + 1;
+const HEADER_LEN: usize = SEED_OFFSET + core::mem::size_of::<u64>();
+
+/// Keys past this amount in a single iteration are not recorded.
+pub const MAX_KEYS_PER_FRAME: usize = 8;
+// LEB128 u64 + keys count + keys.
+const MAX_RECORD_LEN: usize = 10 + 1 + MAX_KEYS_PER_FRAME;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReplayError {
+    /// The data doesn't start with `REPLAY_MAGIC`.
+    BadMagic,
+    UnsupportedVersion(u8),
+    UnknownKey(u8),
+    /// A record has more than `MAX_KEYS_PER_FRAME` keys.
+    TooManyKeys(u8),
+    /// The data ends in the middle of a record.
+    Truncated,
+}
+
+const fn key_code(key: KeyPressedKeys) -> u8 {
+    match key {
+        KeyPressedKeys::Left => 0,
+        KeyPressedKeys::Right => 1,
+        KeyPressedKeys::Shoot => 2,
+        KeyPressedKeys::Restart => 3,
+        KeyPressedKeys::Start => 4,
+        KeyPressedKeys::Pause => 5,
+        KeyPressedKeys::Quit => 6,
+        KeyPressedKeys::Debug => 7,
+    }
+}
+
+const fn key_from_code(code: u8) -> Result<KeyPressedKeys, ReplayError> {
+    match code {
+        0 => Ok(KeyPressedKeys::Left),
+        1 => Ok(KeyPressedKeys::Right),
+        2 => Ok(KeyPressedKeys::Shoot),
+        3 => Ok(KeyPressedKeys::Restart),
+        4 => Ok(KeyPressedKeys::Start),
+        5 => Ok(KeyPressedKeys::Pause),
+        6 => Ok(KeyPressedKeys::Quit),
+        7 => Ok(KeyPressedKeys::Debug),
+        _ => Err(ReplayError::UnknownKey(code)),
+    }
+}
+
+/// The keys pressed during a single loop iteration.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct FrameKeys {
+    keys: [Option<KeyPressedKeys>; MAX_KEYS_PER_FRAME],
+    len: usize,
+}
+
+impl FrameKeys {
+    /// Returns false if the frame is full and `key` was dropped.
+    pub fn push(&mut self, key: KeyPressedKeys) -> bool {
+        if self.len == MAX_KEYS_PER_FRAME {
+            return false;
+        }
+        self.keys[self.len] = Some(key);
+        self.len += 1;
+        true
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = KeyPressedKeys> + '_ {
+        self.keys.iter().flatten().copied()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RecordedFrame {
+    pub delta_ms: u64,
+    pub keys: FrameKeys,
+}
+
+impl RecordedFrame {
+    /// Encodes the record into `out`, returns how many bytes were used.
+    fn encode(&self, out: &mut [u8; MAX_RECORD_LEN]) -> usize {
+        let mut len = 0;
+        let mut delta = self.delta_ms;
+        loop {
+            let byte = u8::try_from(delta & 0x7f).expect("Conversion failed");
+            delta >>= 7;
+            if delta == 0 {
+                out[len] = byte;
+                len += 1;
+                break;
+            }
+            out[len] = byte | 0x80;
+            len += 1;
+        }
+        out[len] = u8::try_from(self.keys.len).expect("Conversion failed");
+        len += 1;
+        for key in self.keys.iter() {
+            out[len] = key_code(key);
+            len += 1;
+        }
+        len
+    }
+}
+
+/// Where a `Recorder` writes the recording to.
+pub trait RecordingSink {
+    fn write_bytes(&mut self, bytes: &[u8]);
+}
+
+/// A sink over a fixed buffer, usable without std. Once full, the rest of the recording is
+/// dropped.
+pub struct SliceSink<'a> {
+    buffer: &'a mut [u8],
+    len: usize,
+    overflowed: bool,
+}
+
+impl<'a> SliceSink<'a> {
+    pub fn new(buffer: &'a mut [u8]) -> Self {
+        Self {
+            buffer,
+            len: 0,
+            overflowed: false,
+        }
+    }
+
+    #[must_use]
+    pub fn written(&self) -> &[u8] {
+        &self.buffer[..self.len]
+    }
+
+    /// True if some records didn't fit in the buffer.
+    #[must_use]
+    pub fn overflowed(&self) -> bool {
+        self.overflowed
+    }
+}
+
+impl RecordingSink for SliceSink<'_> {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        // records are written whole or not at all, so the recording stays readable.
+        if self.overflowed || self.buffer.len() - self.len < bytes.len() {
+            self.overflowed = true;
+            return;
+        }
+        self.buffer[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> RecordingSink for W {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if let Err(e) = self.write_all(bytes) {
+            log::error!("Failed to write the recording: {e}");
+        }
+    }
+}
+
+/// Wraps a framebuffer and records every loop iteration's input to `sink`.
+/// Drawing is forwarded to the wrapped framebuffer.
+pub struct Recorder<F, S> {
+    inner: F,
+    sink: S,
+    delta_ms: u64,
+}
+
+impl<F, S> Recorder<F, S>
+where
+    F: FrameBufferInterface + UserInput,
+    S: RecordingSink,
+{
+    /// `seed` is the one the game is going to use, it's saved in the recording's header.
+    pub fn new(inner: F, mut sink: S, seed: u64) -> Self {
+        sink.write_bytes(&REPLAY_MAGIC);
+        sink.write_bytes(&[REPLAY_VERSION]);
+        sink.write_bytes(&seed.to_le_bytes());
+        Self {
+            inner,
+            sink,
+            delta_ms: 0,
+        }
+    }
+
+    #[must_use]
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+}
+
+impl<F, S> UserInput for Recorder<F, S>
+where
+    F: FrameBufferInterface + UserInput,
+    S: RecordingSink,
+{
+    fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys> {
+        let mut keys = FrameKeys::default();
+        for key in self.inner.get_input() {
+            if !keys.push(key) {
+                log::warn!("Too many keys pressed, not all of them will be recorded.");
+                break;
+            }
+        }
+        let frame = RecordedFrame {
+            delta_ms: self.delta_ms,
+            keys,
+        };
+        let mut encoded = [0u8; MAX_RECORD_LEN];
+        let len = frame.encode(&mut encoded);
+        self.sink.write_bytes(&encoded[..len]);
+        keys.keys.into_iter().flatten()
+    }
+
+    fn frame_delta_ms(&mut self, delta_ms: u64) -> u64 {
+        self.delta_ms = self.inner.frame_delta_ms(delta_ms);
+        self.delta_ms
+    }
+}
+
+/// Parses a recording.
+pub struct ReplayReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    seed: u64,
+}
+
+impl<'a> ReplayReader<'a> {
+    /// # Errors
+    /// If `data` doesn't start with a valid header.
+    pub fn new(data: &'a [u8]) -> Result<Self, ReplayError> {
+        if data.len() < SEED_OFFSET || data[..REPLAY_MAGIC.len()] != REPLAY_MAGIC {
+            return Err(ReplayError::BadMagic);
+        }
+        let version = data[REPLAY_MAGIC.len()];
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(version));
+        }
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(
+            data.get(SEED_OFFSET..HEADER_LEN)
+                .ok_or(ReplayError::Truncated)?,
+        );
+        Ok(Self {
+            data,
+            position: HEADER_LEN,
+            seed: u64::from_le_bytes(seed),
+        })
+    }
+
+    /// The seed the recorded game was using.
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    fn next_byte(&mut self) -> Result<u8, ReplayError> {
+        let byte = *self.data.get(self.position).ok_or(ReplayError::Truncated)?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn read_frame(&mut self) -> Result<RecordedFrame, ReplayError> {
+        let mut delta_ms: u64 = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.next_byte()?;
+            delta_ms |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift >= u64::BITS {
+                return Err(ReplayError::Truncated);
+            }
+        }
+        let count = self.next_byte()?;
+        let mut keys = FrameKeys::default();
+        for _ in 0..count {
+            let key = key_from_code(self.next_byte()?)?;
+            if !keys.push(key) {
+                return Err(ReplayError::TooManyKeys(count));
+            }
+        }
+        Ok(RecordedFrame { delta_ms, keys })
+    }
+}
+
+impl Iterator for ReplayReader<'_> {
+    type Item = Result<RecordedFrame, ReplayError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position >= self.data.len() {
+            return None;
+        }
+        let frame = self.read_frame();
+        if frame.is_err() {
+            // don't keep on reading garbage.
+            self.position = self.data.len();
+        }
+        Some(frame)
+    }
+}
+
+/// Wraps a framebuffer and feeds a recording to the game instead of the wrapped framebuffer's
+/// input. Once the recording is over no key is pressed anymore and the real time is used.
+pub struct Replayer<'a, F> {
+    inner: F,
+    reader: ReplayReader<'a>,
+    current: FrameKeys,
+    finished: bool,
+}
+
+impl<'a, F> Replayer<'a, F>
+where
+    F: FrameBufferInterface + UserInput,
+{
+    pub fn new(inner: F, reader: ReplayReader<'a>) -> Self {
+        Self {
+            inner,
+            reader,
+            current: FrameKeys::default(),
+            finished: false,
+        }
+    }
+
+    /// True once every recorded iteration was replayed.
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+impl<F> UserInput for Replayer<'_, F>
+where
+    F: FrameBufferInterface + UserInput,
+{
+    fn get_input(&mut self) -> impl Iterator<Item = KeyPressedKeys> {
+        // keep the wrapped framebuffer's input (e.g. the window events) flowing.
+        self.inner.get_input().for_each(drop);
+        core::mem::take(&mut self.current)
+            .keys
+            .into_iter()
+            .flatten()
+    }
+
+    fn frame_delta_ms(&mut self, delta_ms: u64) -> u64 {
+        if self.finished {
+            return delta_ms;
+        }
+        match self.reader.next() {
+            Some(Ok(frame)) => {
+                self.current = frame.keys;
+                frame.delta_ms
+            }
+            Some(Err(e)) => {
+                log::error!("Corrupted recording: {e:?}, stopping the replay.");
+                self.finished = true;
+                delta_ms
+            }
+            None => {
+                log::info!("Replay finished.");
+                self.finished = true;
+                delta_ms
+            }
+        }
+    }
+}
+
+/// Both wrappers draw through the wrapped framebuffer: forward every method, so the wrapped
+/// framebuffer's own overrides are still used.
+macro_rules! forward_frame_buffer_interface {
+    (impl<$($generic:tt),*> for $ty:ty) => {
+        impl<$($generic),*> FrameBufferInterface for $ty
+        where
+            F: FrameBufferInterface + UserInput,
+        {
+            fn draw_rect_fill(
+                &mut self,
+                point: &Coordinates,
+                width: u32,
+                height: u32,
+                color: Color,
+            ) {
+                self.inner.draw_rect_fill(point, width, height, color);
+            }
+            fn draw_text(&mut self, text: &str, pos: Coordinates, style: TextStyle) {
+                self.inner.draw_text(text, pos, style);
+            }
+            fn draw_rect(&mut self, point: Coordinates, width: u32, height: u32, color: Color) {
+                self.inner.draw_rect(point, width, height, color);
+            }
+            fn raw_buffer(&mut self) -> &mut [u32] {
+                self.inner.raw_buffer()
+            }
+            fn width(&self) -> usize {
+                self.inner.width()
+            }
+            fn width_u32(&self) -> u32 {
+                self.inner.width_u32()
+            }
+            fn height_u32(&self) -> u32 {
+                self.inner.height_u32()
+            }
+            fn height(&self) -> usize {
+                self.inner.height()
+            }
+            fn stride(&self) -> usize {
+                self.inner.stride()
+            }
+            fn use_pixel(&mut self, x_usize: usize, y_usize: usize, color: Color) {
+                self.inner.use_pixel(x_usize, y_usize, color);
+            }
+            fn viewport(&self) -> Viewport {
+                self.inner.viewport()
+            }
+            fn clip(&self) -> ClipRect {
+                self.inner.clip()
+            }
+            fn set_clip(&mut self, clip: ClipRect) {
+                self.inner.set_clip(clip);
+            }
+            fn read_pixel(&mut self, x_usize: usize, y_usize: usize) -> u32 {
+                self.inner.read_pixel(x_usize, y_usize)
+            }
+            fn display_image(
+                &mut self,
+                top_left: &Coordinates,
+                image: &[u32],
+                width: u32,
+                height: u32,
+            ) {
+                self.inner.display_image(top_left, image, width, height);
+            }
+            fn display_image_scaled(
+                &mut self,
+                top_left: &Coordinates,
+                image: &[u32],
+                width: u32,
+                height: u32,
+                scale: u32,
+                flip_horizontally: bool,
+            ) {
+                self.inner.display_image_scaled(
+                    top_left,
+                    image,
+                    width,
+                    height,
+                    scale,
+                    flip_horizontally,
+                );
+            }
+            fn clear_screen(&mut self) {
+                self.inner.clear_screen();
+            }
+            fn buffer_count(&self) -> usize {
+                self.inner.buffer_count()
+            }
+            fn update(&mut self) {
+                self.inner.update();
+            }
+        }
+    };
+}
+
+forward_frame_buffer_interface!(impl<F, S> for Recorder<F, S>);
+forward_frame_buffer_interface!(impl<'a, F> for Replayer<'a, F>);
+
+#[cfg(test)]
+mod test {
+    use super::{RecordedFrame, RecordingSink, ReplayError, ReplayReader, SliceSink};
+    use crate::{FrameKeys, KeyPressedKeys, REPLAY_MAGIC, REPLAY_VERSION};
+
+    const SEED: u64 = 0x0102_0304_0506_0708;
+    const HEADER: &[u8] = b"SIRP\x03\x08\x07\x06\x05\x04\x03\x02\x01";
+
+    fn frame(delta_ms: u64, keys: &[KeyPressedKeys]) -> RecordedFrame {
+        let mut frame_keys = FrameKeys::default();
+        for key in keys {
+            assert!(frame_keys.push(*key));
+        }
+        RecordedFrame {
+            delta_ms,
+            keys: frame_keys,
+        }
+    }
+
+    fn encode(frames: &[RecordedFrame], buffer: &mut [u8]) -> usize {
+        let mut sink = SliceSink::new(buffer);
+        sink.write_bytes(&REPLAY_MAGIC);
+        sink.write_bytes(&[REPLAY_VERSION]);
+        sink.write_bytes(&SEED.to_le_bytes());
+        for frame in frames {
+            let mut encoded = [0u8; super::MAX_RECORD_LEN];
+            let len = frame.encode(&mut encoded);
+            sink.write_bytes(&encoded[..len]);
+        }
+        assert!(!sink.overflowed());
+        sink.written().len()
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        use KeyPressedKeys::{Left, Pause, Quit, Restart, Right, Shoot, Start};
+        let frames = [
+            frame(0, &[]),
+            frame(33, &[Left, Shoot]),
+            frame(127, &[Right, Left]),
+            frame(128, &[Restart]),
+            frame(40, &[Start, Pause, Quit]),
+            frame(u64::MAX, &[Shoot; 8]),
+        ];
+        let mut buffer = [0u8; 128];
+        let len = encode(&frames, &mut buffer);
+        assert_eq!(&buffer[..HEADER.len()], HEADER);
+        // an idle iteration shorter than 128ms is 2 bytes.
+        assert_eq!(buffer[HEADER.len()..HEADER.len() + 2], [0, 0]);
+
+        let reader = ReplayReader::new(&buffer[..len]).unwrap();
+        assert_eq!(reader.seed(), SEED);
+        let mut decoded = 0;
+        for (read, expected) in reader.zip(frames.iter()) {
+            assert_eq!(read, Ok(*expected));
+            decoded += 1;
+        }
+        assert_eq!(decoded, frames.len());
+    }
+
+    #[test]
+    fn test_invalid_data() {
+        assert!(matches!(
+            ReplayReader::new(b"SIR"),
+            Err(ReplayError::BadMagic)
+        ));
+        assert!(matches!(
+            ReplayReader::new(b"XIRP\x01"),
+            Err(ReplayError::BadMagic)
+        ));
+        assert!(matches!(
+            ReplayReader::new(b"SIRP\x07"),
+            Err(ReplayError::UnsupportedVersion(7))
+        ));
+
+        assert!(matches!(
+            ReplayReader::new(&HEADER[..8]),
+            Err(ReplayError::Truncated)
+        ));
+
+        let read_one = |records: &[u8]| {
+            let mut data = [0u8; 32];
+            data[..HEADER.len()].copy_from_slice(HEADER);
+            data[HEADER.len()..HEADER.len() + records.len()].copy_from_slice(records);
+            let mut reader = ReplayReader::new(&data[..HEADER.len() + records.len()]).unwrap();
+            let first = reader.next();
+            // after an error, the reader stops.
+            assert_eq!(reader.next(), None);
+            first
+        };
+        assert_eq!(
+            read_one(b"\x21\x01\x09"),
+            Some(Err(ReplayError::UnknownKey(9)))
+        );
+        assert_eq!(read_one(b"\x21\x02\x00"), Some(Err(ReplayError::Truncated)));
+        assert_eq!(read_one(b"\xff"), Some(Err(ReplayError::Truncated)));
+        assert_eq!(
+            read_one(&[0, 9, 0, 0, 0, 0, 0, 0, 0, 0, 0]),
+            Some(Err(ReplayError::TooManyKeys(9)))
+        );
+    }
+
+    #[test]
+    fn test_slice_sink_drops_records_that_dont_fit() {
+        let mut buffer = [0u8; 4];
+        let mut sink = SliceSink::new(&mut buffer);
+        sink.write_bytes(&[1, 2, 3]);
+        sink.write_bytes(&[4, 5]);
+        sink.write_bytes(&[6]);
+        assert!(sink.overflowed());
+        assert_eq!(sink.written(), &[1, 2, 3]);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_replay_reproduces_session() {
+        use crate::actor::Actor;
+        use crate::{
+            GameContext, HeadlessFrameBuffer, Level, ManualClock, Recorder, Replayer,
+            XorShift64Star, MAX_LIVES,
+        };
+        use core::time::Duration;
+
+        let clock = ManualClock::new(Duration::from_millis(33));
+        let mut fb = HeadlessFrameBuffer::new();
+        for i in 0..40 {
+            fb.push_input(&[KeyPressedKeys::Shoot]);
+            fb.push_idle(i % 4);
+            fb.push_input(&[KeyPressedKeys::Left]);
+        }
+        let mut recorder = Recorder::new(fb, Vec::new(), SEED);
+        let mut rng = XorShift64Star::new(SEED);
+        let expected = {
+            let mut game = GameContext::new(
+                &mut recorder,
+                0,
+                0,
+                &clock,
+                MAX_LIVES,
+                Level::default(),
+                &mut rng,
+            );
+            for i in 0..150 {
+                // uneven frame times, the replay will run at a different pace.
+                clock.advance_by(Duration::from_millis(10 + (i % 7) * 9));
+                assert_eq!(game.step(), None);
+            }
+            (
+                game.hero.get_coordinates().virtual_x,
+                game.enemies_dead(),
+                game.lives(),
+            )
+        };
+
+        let data = recorder.sink().clone();
+        let clock = ManualClock::new(Duration::from_millis(33));
+        let reader = ReplayReader::new(&data).unwrap();
+        let mut rng = XorShift64Star::new(reader.seed());
+        let mut replayer = Replayer::new(HeadlessFrameBuffer::new(), reader);
+        let mut game = GameContext::new(
+            &mut replayer,
+            0,
+            0,
+            &clock,
+            MAX_LIVES,
+            Level::default(),
+            &mut rng,
+        );
+        for _ in 0..150 {
+            clock.advance();
+            assert_eq!(game.step(), None);
+        }
+        let actual = (
+            game.hero.get_coordinates().virtual_x,
+            game.enemies_dead(),
+            game.lives(),
+        );
+        assert_eq!(expected, actual);
+        game.step();
+        assert!(replayer.is_finished());
+    }
+
+    /// A machine that can't keep up: every frame lasts longer than asked, by a varying amount.
+    #[cfg(feature = "std")]
+    struct LaggingClock {
+        clock: crate::ManualClock,
+        frames: core::cell::Cell<u64>,
+    }
+
+    #[cfg(feature = "std")]
+    impl crate::TimeManagerInterface for LaggingClock {
+        fn now(&self) -> core::time::Duration {
+            self.clock.now()
+        }
+
+        fn sleep(&self, duration: core::time::Duration) {
+            let frames = self.frames.get();
+            self.frames.set(frames + 1);
+            let lag = core::time::Duration::from_millis(5 + frames % 4 * 5);
+            self.clock.advance_by(duration + lag);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_replay_reproduces_screens() {
+        use crate::high_score::HighScoreTable;
+        use crate::{
+            play, screens, GameState, HeadlessFrameBuffer, ManualClock, Recorder, Replayer,
+            XorShift64Star,
+        };
+        use core::time::Duration;
+
+        // title, a lost game, game over, title, a new game quit right away, title and quit: the
+        // game over screen runs its full length, the keys after it must land on the same screens.
+        let clock = LaggingClock {
+            clock: ManualClock::new(Duration::ZERO),
+            frames: core::cell::Cell::new(0),
+        };
+        let table = HighScoreTable::new();
+        let mut recorder = Recorder::new(HeadlessFrameBuffer::new(), Vec::new(), SEED);
+        let mut rng = XorShift64Star::new(SEED);
+        recorder.inner.push_idle(1);
+        recorder.inner.push_input(&[KeyPressedKeys::Start]);
+        assert_eq!(
+            screens::title(&mut recorder, &clock, &table),
+            Some(GameState::Playing)
+        );
+        assert_eq!(
+            play(&mut recorder, &clock, &mut rng, 0),
+            GameState::GameOver(0)
+        );
+        screens::game_over(&mut recorder, &clock, 0);
+        recorder.inner.push_idle(1);
+        recorder.inner.push_input(&[KeyPressedKeys::Start]);
+        assert_eq!(
+            screens::title(&mut recorder, &clock, &table),
+            Some(GameState::Playing)
+        );
+        recorder.inner.push_idle(5);
+        recorder.inner.push_input(&[KeyPressedKeys::Quit]);
+        assert_eq!(play(&mut recorder, &clock, &mut rng, 0), GameState::Title);
+        recorder.inner.push_idle(1);
+        recorder.inner.push_input(&[KeyPressedKeys::Quit]);
+        assert_eq!(screens::title(&mut recorder, &clock, &table), None);
+
+        let data = recorder.sink().clone();
+        let clock = ManualClock::new(Duration::ZERO);
+        let reader = ReplayReader::new(&data).unwrap();
+        let mut rng = XorShift64Star::new(reader.seed());
+        let mut replayer = Replayer::new(HeadlessFrameBuffer::new(), reader);
+        assert_eq!(
+            screens::title(&mut replayer, &clock, &table),
+            Some(GameState::Playing)
+        );
+        assert_eq!(
+            play(&mut replayer, &clock, &mut rng, 0),
+            GameState::GameOver(0)
+        );
+        screens::game_over(&mut replayer, &clock, 0);
+        assert_eq!(
+            screens::title(&mut replayer, &clock, &table),
+            Some(GameState::Playing)
+        );
+        assert_eq!(play(&mut replayer, &clock, &mut rng, 0), GameState::Title);
+        assert_eq!(screens::title(&mut replayer, &clock, &table), None);
+        assert!(replayer.reader.next().is_none());
+    }
+}
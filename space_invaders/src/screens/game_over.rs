@@ -0,0 +1,32 @@
+use super::{draw_centered, draw_heading, Line, ScreenLoop};
+use crate::framebuffer::color::WHITE_COLOR;
+use crate::{FrameBufferInterface, KeyPressedKeys, TimeManagerInterface, UserInput};
+use core::time::Duration;
+
+const TITLE_Y: u32 = 250;
+const SCORE_Y: u32 = 320;
+const HELP_Y: u32 = 390;
+// unless a key cuts it short.
+const GAME_OVER_DURATION: Duration = Duration::from_secs(3);
+
+/// Shows the final score for a while.
+pub(crate) fn game_over<F>(fb: &mut F, time_manager: &impl TimeManagerInterface, score: u32)
+where
+    F: FrameBufferInterface + UserInput,
+{
+    let mut screen = ScreenLoop::new(time_manager);
+    loop {
+        let keys = screen.next_frame(fb);
+        let skipped = keys.contains(KeyPressedKeys::Start) || keys.contains(KeyPressedKeys::Shoot);
+        if skipped || screen.elapsed() >= GAME_OVER_DURATION {
+            return;
+        }
+
+        fb.clear_screen();
+        draw_heading(fb, "GAME OVER", TITLE_Y);
+        let line = Line::from_fmt(format_args!("Score: {score}"));
+        draw_centered(fb, &line, SCORE_Y, WHITE_COLOR);
+        draw_centered(fb, "enter or space: continue", HELP_Y, WHITE_COLOR);
+        fb.update();
+    }
+}
@@ -0,0 +1,140 @@
+use super::{draw_centered, draw_table, Line, ScreenLoop};
+use crate::framebuffer::color::{SHOT_COLOR, WHITE_COLOR};
+use crate::framebuffer::text::TextStyle;
+use crate::high_score::{HighScoreTable, INITIALS_LEN};
+use crate::{
+    Color, Coordinates, FrameBufferInterface, KeyPressedKeys, TimeManagerInterface, UserInput,
+    SCREEN_WIDTH,
+};
+
+const TITLE_Y: u32 = 150;
+const INITIALS_Y: u32 = 220;
+const HELP_Y: u32 = 270;
+const TABLE_Y: u32 = 340;
+// the letter being picked.
+const SELECTED_COLOR: Color = SHOT_COLOR;
+
+/// Asks the player for their initials, after a game good enough for the high score table.
+///
+/// Left and right pick the letter, shoot or start confirm it and move to the next one; restart
+/// keeps the remaining letters as they are.
+pub(crate) fn enter_initials<F>(
+    fb: &mut F,
+    time_manager: &impl TimeManagerInterface,
+    table: &HighScoreTable,
+    score: u32,
+) -> [u8; INITIALS_LEN]
+where
+    F: FrameBufferInterface + UserInput,
+{
+    let mut screen = ScreenLoop::new(time_manager);
+    let mut initials = [b'A'; INITIALS_LEN];
+    let mut selected = 0;
+    loop {
+        let keys = screen.next_frame(fb);
+        let letter = &mut initials[selected];
+        if keys.contains(KeyPressedKeys::Left) {
+            *letter = previous_letter(*letter);
+        }
+        if keys.contains(KeyPressedKeys::Right) {
+            *letter = next_letter(*letter);
+        }
+        if keys.contains(KeyPressedKeys::Shoot) || keys.contains(KeyPressedKeys::Start) {
+            selected += 1;
+        }
+        if keys.contains(KeyPressedKeys::Restart) || selected == INITIALS_LEN {
+            return initials;
+        }
+
+        fb.clear_screen();
+        draw(fb, table, score, initials, selected);
+        fb.update();
+    }
+}
+
+const fn next_letter(letter: u8) -> u8 {
+    if letter == b'Z' {
+        b'A'
+    } else {
+        letter + 1
+    }
+}
+
+const fn previous_letter(letter: u8) -> u8 {
+    if letter == b'A' {
+        b'Z'
+    } else {
+        letter - 1
+    }
+}
+
+fn draw(
+    fb: &mut impl FrameBufferInterface,
+    table: &HighScoreTable,
+    score: u32,
+    initials: [u8; INITIALS_LEN],
+    selected: usize,
+) {
+    let title = Line::from_fmt(format_args!("New high score: {score}"));
+    draw_centered(fb, &title, TITLE_Y, WHITE_COLOR);
+
+    // the prompt and the letters, a space between each, centered together.
+    let style = TextStyle::new(WHITE_COLOR);
+    let prompt = "Enter your initials: ";
+    let letters_len = u32::try_from(INITIALS_LEN * 2 - 1).expect("Conversion failed");
+    let width = style.width(prompt) + letters_len * style.letter_width();
+    let mut x = SCREEN_WIDTH.saturating_sub(width) / 2;
+    fb.draw_text(prompt, Coordinates::new(x, INITIALS_Y), style);
+    x += style.width(prompt);
+    for (i, letter) in initials.iter().enumerate() {
+        let color = if i == selected {
+            SELECTED_COLOR
+        } else {
+            WHITE_COLOR
+        };
+        let mut letter_buffer = [0u8; 4];
+        let letter = char::from(*letter).encode_utf8(&mut letter_buffer);
+        fb.draw_text(
+            letter,
+            Coordinates::new(x, INITIALS_Y),
+            style.with_color(color),
+        );
+        x += 2 * style.letter_width();
+    }
+    draw_centered(
+        fb,
+        "a/d: change letter - space: next letter",
+        HELP_Y,
+        WHITE_COLOR,
+    );
+    draw_table(fb, table, TABLE_Y);
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::enter_initials;
+    use crate::high_score::HighScoreTable;
+    use crate::{HeadlessFrameBuffer, KeyPressedKeys, ManualClock};
+    use core::time::Duration;
+
+    #[test]
+    fn test_pick_initials() {
+        let clock = ManualClock::new(Duration::from_millis(33));
+        let mut fb = HeadlessFrameBuffer::new();
+        // still shooting from the game: ignored until released.
+        fb.push_input(&[KeyPressedKeys::Shoot]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Left]);
+        fb.push_input(&[KeyPressedKeys::Left]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Left]);
+        fb.push_input(&[KeyPressedKeys::Shoot]);
+        fb.push_input(&[KeyPressedKeys::Right]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Start]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Restart]);
+        let initials = enter_initials(&mut fb, &clock, &HighScoreTable::new(), 100);
+        assert_eq!(&initials, b"YBA");
+    }
+}
@@ -0,0 +1,169 @@
+//! Everything shown outside of the game itself: title, pause, game over and high score entry.
+//! Text is drawn with `FrameBufferInterface::draw_text`, centered on the screen.
+
+mod game_over;
+mod initials_entry;
+mod paused;
+mod title;
+
+pub(crate) use game_over::game_over;
+pub(crate) use initials_entry::enter_initials;
+pub(crate) use paused::paused;
+pub(crate) use title::title;
+
+use crate::framebuffer::color::{SHOT_COLOR, WHITE_COLOR};
+use crate::framebuffer::text::{TextAlign, TextSize, TextStyle, TextWeight};
+use crate::high_score::HighScoreTable;
+use crate::{
+    Color, Coordinates, FrameBufferInterface, KeyPressedKeys, TextBuffer, TimeManagerInterface,
+    UserInput, FRAME_DURATION, SCREEN_WIDTH,
+};
+use core::time::Duration;
+
+// the longest line: the initials entry title, with the largest score.
+const MAX_LINE_LEN: usize = 40;
+type Line = TextBuffer<MAX_LINE_LEN>;
+const TABLE_LINE_HEIGHT: u32 = 30;
+
+const KEYS: [KeyPressedKeys; 7] = [
+    KeyPressedKeys::Left,
+    KeyPressedKeys::Right,
+    KeyPressedKeys::Shoot,
+    KeyPressedKeys::Restart,
+    KeyPressedKeys::Start,
+    KeyPressedKeys::Pause,
+    KeyPressedKeys::Quit,
+];
+
+/// The keys that went down this frame.
+#[derive(Debug, Copy, Clone, Default)]
+pub(crate) struct PressedKeys {
+    pressed: [bool; KEYS.len()],
+}
+
+impl PressedKeys {
+    fn from_input(input: impl Iterator<Item = KeyPressedKeys>) -> Self {
+        let mut keys = Self::default();
+        for key in input {
+            if let Some(i) = KEYS.iter().position(|k| *k == key) {
+                keys.pressed[i] = true;
+            }
+        }
+        keys
+    }
+
+    pub(crate) fn contains(self, key: KeyPressedKeys) -> bool {
+        KEYS.iter()
+            .zip(self.pressed)
+            .any(|(k, pressed)| *k == key && pressed)
+    }
+}
+
+/// Paces a screen at `FPS` and tells which keys were pressed on every frame.
+/// Keys only count when pressed, holding them does nothing: keys still held when the screen
+/// shows up, e.g. from the game, don't count until released.
+pub(crate) struct ScreenLoop<'a, T> {
+    time_manager: &'a T,
+    last_frame: Duration,
+    // the frame deltas given by `frame_delta_ms`, so a replay lasts as many frames.
+    elapsed: Duration,
+    held: PressedKeys,
+}
+
+impl<'a, T: TimeManagerInterface> ScreenLoop<'a, T> {
+    pub(crate) fn new(time_manager: &'a T) -> Self {
+        let now = time_manager.now();
+        Self {
+            time_manager,
+            last_frame: now,
+            elapsed: Duration::ZERO,
+            held: PressedKeys {
+                pressed: [true; KEYS.len()],
+            },
+        }
+    }
+
+    /// Waits for the next frame, then reads the input.
+    pub(crate) fn next_frame(&mut self, fb: &mut impl UserInput) -> PressedKeys {
+        self.time_manager
+            .wait_for_tick(self.last_frame, FRAME_DURATION);
+        let now = self.time_manager.now();
+        let delta_ms = fb.frame_delta_ms(
+            u64::try_from(self.time_manager.since(self.last_frame).as_millis())
+                .expect("Conversion failed"),
+        );
+        self.last_frame = now;
+        self.elapsed = self.elapsed.saturating_add(Duration::from_millis(delta_ms));
+
+        let held = PressedKeys::from_input(fb.get_input());
+        let mut pressed = held;
+        for (pressed, was_held) in pressed.pressed.iter_mut().zip(self.held.pressed) {
+            *pressed &= !was_held;
+        }
+        self.held = held;
+        pressed
+    }
+
+    /// How long the screen has been showing, as told by `frame_delta_ms`.
+    pub(crate) const fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+const HEADING_STYLE: TextStyle = TextStyle::new(SHOT_COLOR)
+    .with_size(TextSize::Large)
+    .with_weight(TextWeight::Bold)
+    .with_align(TextAlign::Center);
+
+pub(crate) fn draw_centered(fb: &mut impl FrameBufferInterface, text: &str, y: u32, color: Color) {
+    let style = TextStyle::new(color).with_align(TextAlign::Center);
+    fb.draw_text(text, Coordinates::new(SCREEN_WIDTH / 2, y), style);
+}
+
+/// The screen's title, bigger than the rest.
+pub(crate) fn draw_heading(fb: &mut impl FrameBufferInterface, text: &str, y: u32) {
+    fb.draw_text(text, Coordinates::new(SCREEN_WIDTH / 2, y), HEADING_STYLE);
+}
+
+/// One line per score, starting from `y`.
+fn draw_table(fb: &mut impl FrameBufferInterface, table: &HighScoreTable, y: u32) {
+    let mut y = y;
+    for (i, entry) in table.iter().enumerate() {
+        let line = Line::from_fmt(format_args!(
+            "{:>2}. {} {:>10}",
+            i + 1,
+            entry.initials_str(),
+            entry.score
+        ));
+        draw_centered(fb, &line, y, WHITE_COLOR);
+        y += TABLE_LINE_HEIGHT;
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::ScreenLoop;
+    use crate::{HeadlessFrameBuffer, KeyPressedKeys, ManualClock, FRAME_DURATION};
+    use core::time::Duration;
+
+    #[test]
+    fn test_held_keys_count_once() {
+        let clock = ManualClock::new(FRAME_DURATION);
+        let mut fb = HeadlessFrameBuffer::new();
+        fb.push_input(&[KeyPressedKeys::Start]);
+        fb.push_idle(1);
+        fb.push_input(&[KeyPressedKeys::Start]);
+        fb.push_input(&[KeyPressedKeys::Start, KeyPressedKeys::Left]);
+        let mut screen = ScreenLoop::new(&clock);
+        // still held from before the screen.
+        assert!(!screen.next_frame(&mut fb).contains(KeyPressedKeys::Start));
+        assert!(!screen.next_frame(&mut fb).contains(KeyPressedKeys::Start));
+        assert!(screen.next_frame(&mut fb).contains(KeyPressedKeys::Start));
+        let keys = screen.next_frame(&mut fb);
+        assert!(!keys.contains(KeyPressedKeys::Start));
+        assert!(keys.contains(KeyPressedKeys::Left));
+        // whole milliseconds, as given to `frame_delta_ms`.
+        let frame_ms = u64::try_from(FRAME_DURATION.as_millis()).unwrap();
+        assert_eq!(screen.elapsed(), Duration::from_millis(4 * frame_ms));
+    }
+}
@@ -0,0 +1,34 @@
+use super::{draw_centered, draw_heading, ScreenLoop};
+use crate::framebuffer::color::WHITE_COLOR;
+use crate::{
+    FrameBufferInterface, GameContext, GameState, KeyPressedKeys, RandomGenerator,
+    TimeManagerInterface, UserInput,
+};
+
+const TITLE_Y: u32 = 300;
+const HELP_Y: u32 = 350;
+
+/// Shows the game standing still until it's resumed (`GameState::Playing`) or quit
+/// (`GameState::Title`).
+pub(crate) fn paused<T, F, R>(game: &mut GameContext<'_, T, F, R>) -> GameState
+where
+    F: FrameBufferInterface + UserInput,
+    T: TimeManagerInterface,
+    R: RandomGenerator,
+{
+    let mut screen = ScreenLoop::new(game.time_manager);
+    loop {
+        let keys = screen.next_frame(game.frame_buffer());
+        if keys.contains(KeyPressedKeys::Quit) {
+            return GameState::Title;
+        }
+        if keys.contains(KeyPressedKeys::Pause) || keys.contains(KeyPressedKeys::Start) {
+            return GameState::Playing;
+        }
+
+        game.render_with(|fb| {
+            draw_heading(fb, "PAUSED", TITLE_Y);
+            draw_centered(fb, "p or enter: resume - q: quit", HELP_Y, WHITE_COLOR);
+        });
+    }
+}
@@ -0,0 +1,45 @@
+use super::{draw_centered, draw_heading, draw_table, ScreenLoop};
+use crate::framebuffer::color::WHITE_COLOR;
+use crate::high_score::HighScoreTable;
+use crate::{FrameBufferInterface, GameState, KeyPressedKeys, TimeManagerInterface, UserInput};
+
+const TITLE_Y: u32 = 120;
+const HELP_Y: u32 = 190;
+const HIGH_SCORES_Y: u32 = 280;
+const TABLE_Y: u32 = 320;
+
+/// Shows the high scores until the player starts a game, returns `None` if they quit instead.
+pub(crate) fn title<F>(
+    fb: &mut F,
+    time_manager: &impl TimeManagerInterface,
+    table: &HighScoreTable,
+) -> Option<GameState>
+where
+    F: FrameBufferInterface + UserInput,
+{
+    let mut screen = ScreenLoop::new(time_manager);
+    loop {
+        let keys = screen.next_frame(fb);
+        if keys.contains(KeyPressedKeys::Quit) {
+            return None;
+        }
+        if keys.contains(KeyPressedKeys::Start) || keys.contains(KeyPressedKeys::Shoot) {
+            return Some(GameState::Playing);
+        }
+
+        fb.clear_screen();
+        draw(fb, table);
+        fb.update();
+    }
+}
+
+fn draw(fb: &mut impl FrameBufferInterface, table: &HighScoreTable) {
+    draw_heading(fb, "SPACE INVADERS", TITLE_Y);
+    draw_centered(fb, "enter or space: start - q: quit", HELP_Y, WHITE_COLOR);
+    if table.is_empty() {
+        draw_centered(fb, "No high scores yet", HIGH_SCORES_Y, WHITE_COLOR);
+    } else {
+        draw_centered(fb, "High scores", HIGH_SCORES_Y, WHITE_COLOR);
+        draw_table(fb, table, TABLE_Y);
+    }
+}
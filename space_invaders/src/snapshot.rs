@@ -0,0 +1,249 @@
+//! Golden image tests: frames are rendered offscreen and compared against the reference PNGs
+//! in `tests/snapshots`.
+//!
+//! Pixels are stored as RGBA where A is the high byte of the framebuffer's `u32`, so a reference
+//! round-trips exactly. To (re)generate the references, run the tests with `UPDATE_SNAPSHOTS=1`
+//! and check the new images by eye before committing them.
+//! On a mismatch, `<name>.actual.png` and `<name>.diff.png` (differing pixels in red) are
+//! written next to the reference.
+
+use crate::actor::{Actor, Barricade};
+use crate::framebuffer::color::WHITE_COLOR;
+use crate::screens;
+use crate::{
+    Color, Coordinates, FrameBufferInterface, GameContext, HeadlessFrameBuffer, HighScoreEntry,
+    HighScoreTable, KeyPressedKeys, Level, ManualClock, TextAlign, TextSize, TextStyle, TextWeight,
+    XorShift64Star, MAX_LIVES, SCREEN_HEIGHT, SCREEN_WIDTH,
+};
+use core::fmt::Write;
+use core::time::Duration;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+// how many differing pixels are listed in the report.
+const MAX_REPORTED_PIXELS: usize = 10;
+
+fn snapshot_path(name: &str, suffix: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("snapshots")
+        .join(format!("{name}{suffix}.png"))
+}
+
+fn write_png(path: &Path, pixels: &[u32], width: u32, height: u32) {
+    let file =
+        File::create(path).unwrap_or_else(|e| panic!("Failed to create {}: {e}", path.display()));
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let data: Vec<u8> = pixels
+        .iter()
+        .flat_map(|pixel| {
+            let [a, r, g, b] = pixel.to_be_bytes();
+            [r, g, b, a]
+        })
+        .collect();
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .unwrap_or_else(|e| panic!("Failed to write {}: {e}", path.display()));
+}
+
+fn read_png(path: &Path) -> Option<(Vec<u32>, u32, u32)> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let mut reader = decoder.read_info().expect("Invalid png");
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).expect("Invalid png");
+    assert_eq!(
+        (info.color_type, info.bit_depth),
+        (png::ColorType::Rgba, png::BitDepth::Eight),
+        "{} must be an 8 bit RGBA image",
+        path.display()
+    );
+    let pixels = data[..info.buffer_size()]
+        .chunks_exact(4)
+        .map(|p| u32::from_be_bytes([p[3], p[0], p[1], p[2]]))
+        .collect();
+    Some((pixels, info.width, info.height))
+}
+
+/// Compares `pixels` against the reference image `name`, panics with a report on mismatch.
+pub(crate) fn assert_snapshot(name: &str, pixels: &[u32], width: u32, height: u32) {
+    let reference_path = snapshot_path(name, "");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        write_png(&reference_path, pixels, width, height);
+        return;
+    }
+    let Some((expected, expected_width, expected_height)) = read_png(&reference_path) else {
+        panic!(
+            "Missing reference {}, run the tests with UPDATE_SNAPSHOTS=1.",
+            reference_path.display()
+        );
+    };
+    assert_eq!(
+        (expected_width, expected_height),
+        (width, height),
+        "{name}: the reference has a different size"
+    );
+
+    let mut report = String::new();
+    let mut differing = 0;
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (u32::MAX, u32::MAX, 0, 0);
+    let mut diff = vec![0u32; pixels.len()];
+    for (index, (actual, expected)) in pixels.iter().zip(expected.iter()).enumerate() {
+        if actual == expected {
+            // dimmed, so the differences stand out.
+            diff[index] = 0xff00_0000 | ((actual & 0x00fe_fefe) >> 2);
+            continue;
+        }
+        diff[index] = 0xffff_0000;
+        let index = u32::try_from(index).expect("Conversion failed");
+        let (x, y) = (index % width, index / width);
+        (min_x, min_y) = (min_x.min(x), min_y.min(y));
+        (max_x, max_y) = (max_x.max(x), max_y.max(y));
+        if differing < MAX_REPORTED_PIXELS {
+            writeln!(
+                report,
+                "  ({x}, {y}): expected {expected:#010x}, got {actual:#010x}"
+            )
+            .unwrap();
+        }
+        differing += 1;
+    }
+    if differing == 0 {
+        return;
+    }
+    write_png(&snapshot_path(name, ".actual"), pixels, width, height);
+    write_png(&snapshot_path(name, ".diff"), &diff, width, height);
+    panic!(
+        "{name}: {differing} pixels differ, in the area ({min_x}, {min_y}) - ({max_x}, {max_y}):\n\
+         {report}see {} and {}",
+        snapshot_path(name, ".actual").display(),
+        snapshot_path(name, ".diff").display(),
+    );
+}
+
+const FRAME: Duration = Duration::from_millis(33);
+const SEED: u64 = 42;
+
+#[test]
+fn test_initial_frame() {
+    let clock = ManualClock::new(FRAME);
+    let mut fb = HeadlessFrameBuffer::new();
+    let mut rng = XorShift64Star::new(SEED);
+    GameContext::new(
+        &mut fb,
+        1234,
+        56,
+        &clock,
+        MAX_LIVES,
+        Level::default(),
+        &mut rng,
+    )
+    .render();
+    assert_snapshot("initial_frame", fb.buffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+}
+
+#[test]
+fn test_initial_frame_letterboxed() {
+    let clock = ManualClock::new(FRAME);
+    let mut fb = HeadlessFrameBuffer::with_size(640, 480);
+    let mut rng = XorShift64Star::new(SEED);
+    GameContext::new(
+        &mut fb,
+        1234,
+        56,
+        &clock,
+        MAX_LIVES,
+        Level::default(),
+        &mut rng,
+    )
+    .render();
+    assert_snapshot("initial_frame_640x480", fb.buffer(), 640, 480);
+}
+
+#[test]
+fn test_frame_after_shooting() {
+    let clock = ManualClock::new(FRAME);
+    let mut fb = HeadlessFrameBuffer::new();
+    for _ in 0..12 {
+        fb.push_input(&[KeyPressedKeys::Left, KeyPressedKeys::Shoot]);
+        fb.push_idle(3);
+    }
+    {
+        let mut rng = XorShift64Star::new(SEED);
+        let mut game = GameContext::new(
+            &mut fb,
+            0,
+            0,
+            &clock,
+            MAX_LIVES - 1,
+            Level::default(),
+            &mut rng,
+        );
+        for _ in 0..60 {
+            clock.advance();
+            assert_eq!(game.step(), None);
+        }
+        game.render();
+    }
+    assert_snapshot("after_shooting", fb.buffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+}
+
+#[test]
+fn test_primitives() {
+    let mut fb = HeadlessFrameBuffer::new();
+    fb.draw_rect(Coordinates::new(10, 10), 100, 40, WHITE_COLOR);
+    fb.draw_rect_fill(&Coordinates::new(20, 20), 30, 20, Color::new(10, 200, 30));
+    let style = TextStyle::new(Color::new(250, 0, 100)).with_background(Color::new(0, 0, 255));
+    fb.draw_text("Snapshot 0123", Coordinates::new(120, 15), style);
+    let style = TextStyle::new(WHITE_COLOR)
+        .with_size(TextSize::Large)
+        .with_weight(TextWeight::Bold)
+        .with_align(TextAlign::Right);
+    // not in the font, drawn as the fallback.
+    fb.draw_text(
+        "Right \u{263a}",
+        Coordinates::new(SCREEN_WIDTH - 10, 15),
+        style,
+    );
+    for barricade in &Barricade::create_barricades() {
+        barricade.draw(&mut fb);
+    }
+    assert_snapshot("primitives", fb.buffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+}
+
+#[test]
+fn test_initials_entry() {
+    let clock = ManualClock::new(FRAME);
+    let mut fb = HeadlessFrameBuffer::new();
+    fb.push_idle(1);
+    fb.push_input(&[KeyPressedKeys::Right]);
+    fb.push_input(&[KeyPressedKeys::Shoot]);
+    fb.push_input(&[KeyPressedKeys::Restart]);
+    let mut table = HighScoreTable::new();
+    for (initials, score) in [(*b"ABC", 4321), (*b"XYZ", 90)] {
+        table.insert(HighScoreEntry { initials, score });
+    }
+    screens::enter_initials(&mut fb, &clock, &table, 1234);
+    assert_snapshot("initials_entry", fb.buffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+}
+
+#[test]
+fn test_title() {
+    let clock = ManualClock::new(FRAME);
+    let mut fb = HeadlessFrameBuffer::new();
+    fb.push_idle(1);
+    fb.push_input(&[KeyPressedKeys::Start]);
+    let mut table = HighScoreTable::new();
+    table.insert(HighScoreEntry {
+        initials: *b"ABC",
+        score: 4321,
+    });
+    assert_eq!(
+        screens::title(&mut fb, &clock, &table),
+        Some(crate::GameState::Playing)
+    );
+    assert_snapshot("title", fb.buffer(), SCREEN_WIDTH, SCREEN_HEIGHT);
+}
@@ -0,0 +1,139 @@
+use core::fmt;
+use core::ops::Deref;
+
+/// A string of at most `N` bytes, kept inline: `write!` into it, then draw it with
+/// `FrameBufferInterface::draw_text`. What doesn't fit is cut off, on a char boundary, and the
+/// write fails: the text written so far is still there.
+#[derive(Copy, Clone)]
+pub struct TextBuffer<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> TextBuffer<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    /// `args` formatted, cut off past `N` bytes.
+    #[must_use]
+    pub fn from_fmt(args: fmt::Arguments) -> Self {
+        let mut text = Self::new();
+        // cut off, it's still fine to show.
+        let _ = fmt::Write::write_fmt(&mut text, args);
+        text
+    }
+
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        // only whole chars are written.
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Appends `s`, or as much of it as fits.
+    ///
+    /// # Errors
+    ///
+    /// - `fmt::Error` if it was cut off.
+    pub fn push_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(N - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.bytes[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        if end == s.len() {
+            Ok(())
+        } else {
+            Err(fmt::Error)
+        }
+    }
+}
+
+impl<const N: usize> Default for TextBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for TextBuffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s)
+    }
+}
+
+impl<const N: usize> Deref for TextBuffer<N> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<const N: usize> fmt::Display for TextBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl<const N: usize> fmt::Debug for TextBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TextBuffer;
+    use core::fmt::Write;
+
+    #[test]
+    fn test_write() {
+        let mut text = TextBuffer::<16>::new();
+        write!(text, "Score: {}", 42).unwrap();
+        assert_eq!(text.as_str(), "Score: 42");
+        text.clear();
+        assert!(text.is_empty());
+        assert_eq!(
+            &*TextBuffer::<16>::from_fmt(format_args!("{}-{}", 1, 2)),
+            "1-2"
+        );
+    }
+
+    #[test]
+    fn test_cut_off() {
+        let mut text = TextBuffer::<8>::new();
+        assert!(write!(text, "{}", 1_234_567_890).is_err());
+        assert_eq!(text.as_str(), "12345678");
+        assert!(text.push_str("9").is_err());
+
+        // 'é' takes 2 bytes: not split.
+        let mut text = TextBuffer::<4>::new();
+        assert!(text.push_str("abcé").is_err());
+        assert_eq!(text.as_str(), "abc");
+        assert_eq!(text.capacity(), 4);
+    }
+}
@@ -1,3 +1,4 @@
+use core::cell::Cell;
 use core::ops::Sub;
 use core::time::Duration;
 
@@ -9,6 +10,57 @@ pub trait TimeManagerInterface {
     fn since(&self, time_in_the_past: Duration) -> Duration {
         self.now().sub(time_in_the_past)
     }
+
+    /// block for `duration`. By default it spins on `now`.
+    fn sleep(&self, duration: Duration) {
+        let start = self.now();
+        while self.since(start) < duration {
+            core::hint::spin_loop();
+        }
+    }
+
+    /// block until the next tick, `period` after `last_tick`. By default it sleeps for what's left
+    /// of `period`, a platform with a periodic timer interrupt can wait for it instead.
+    fn wait_for_tick(&self, last_tick: Duration, period: Duration) {
+        self.sleep(period.saturating_sub(self.since(last_tick)));
+    }
+}
+
+/// A clock that only moves forward when asked to, used to run the game deterministically
+/// (e.g. in tests, without a window).
+pub struct ManualClock {
+    now: Cell<Duration>,
+    step: Duration,
+}
+
+impl ManualClock {
+    /// `step` is how much time passes on every call to `advance`.
+    #[must_use]
+    pub const fn new(step: Duration) -> Self {
+        Self {
+            now: Cell::new(Duration::ZERO),
+            step,
+        }
+    }
+
+    pub fn advance(&self) {
+        self.advance_by(self.step);
+    }
+
+    pub fn advance_by(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+
+impl TimeManagerInterface for ManualClock {
+    fn now(&self) -> Duration {
+        self.now.get()
+    }
+
+    /// Sleeping doesn't wait, it just moves the clock forward.
+    fn sleep(&self, duration: Duration) {
+        self.advance_by(duration);
+    }
 }
 
 #[cfg(feature = "std")]
@@ -34,5 +86,9 @@ mod std_time {
                 .duration_since(SystemTime::UNIX_EPOCH)
                 .expect("Failed to get current time")
         }
+
+        fn sleep(&self, duration: Duration) {
+            std::thread::sleep(duration);
+        }
     }
 }